@@ -0,0 +1,271 @@
+use crate::api::models::ErrorResponse;
+use crate::services::{captcha::CaptchaError, node_cli::NodeCliError, pow::PowError};
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Stable, machine-readable error code sent as `code` in every error body.
+/// Clients should branch on this rather than on the `error` text. Each code
+/// always comes with the same HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidAddress,
+    InvalidDeployId,
+    InvalidIdempotencyKey,
+    BalanceAboveLimit,
+    CaptchaRequired,
+    PowInvalid,
+    Unauthorized,
+    CaptchaRejected,
+    PowRejected,
+    JobNotFound,
+    DeployNotFound,
+    PowDisabled,
+    RequestTimeout,
+    IdempotencyConflict,
+    CooldownActive,
+    RateLimited,
+    InternalError,
+    NodeError,
+    FaucetPaused,
+    FaucetOutOfFunds,
+    QueueFull,
+    NodeUnavailable,
+    CaptchaUnavailable,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::InvalidRequest
+            | Self::InvalidAddress
+            | Self::InvalidDeployId
+            | Self::InvalidIdempotencyKey
+            | Self::BalanceAboveLimit
+            | Self::CaptchaRequired
+            | Self::PowInvalid => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::CaptchaRejected | Self::PowRejected => StatusCode::FORBIDDEN,
+            Self::JobNotFound | Self::DeployNotFound | Self::PowDisabled => StatusCode::NOT_FOUND,
+            Self::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
+            Self::IdempotencyConflict => StatusCode::CONFLICT,
+            Self::CooldownActive | Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NodeError => StatusCode::BAD_GATEWAY,
+            Self::FaucetPaused
+            | Self::FaucetOutOfFunds
+            | Self::QueueFull
+            | Self::NodeUnavailable
+            | Self::CaptchaUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// Human-readable summary, sent as `error`.
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::InvalidRequest => "FAUCET: Invalid request",
+            Self::InvalidAddress => "FAUCET: Invalid address",
+            Self::InvalidDeployId => "FAUCET: Invalid deploy ID",
+            Self::InvalidIdempotencyKey => "FAUCET: Invalid Idempotency-Key",
+            Self::BalanceAboveLimit => "FAUCET: Balance above faucet limit",
+            Self::CaptchaRequired | Self::CaptchaRejected | Self::CaptchaUnavailable => {
+                "FAUCET: CAPTCHA verification failed"
+            }
+            Self::PowInvalid | Self::PowRejected => "FAUCET: Proof-of-work verification failed",
+            Self::Unauthorized => "FAUCET: Unauthorized",
+            Self::JobNotFound => "FAUCET: Job not found",
+            Self::DeployNotFound => "FAUCET: Deploy not found",
+            Self::PowDisabled => "FAUCET: Proof of work is not enabled",
+            Self::IdempotencyConflict => "FAUCET: Idempotency-Key conflict",
+            Self::CooldownActive => "FAUCET: Cooldown period active",
+            Self::RateLimited => "FAUCET: Rate limit exceeded",
+            Self::InternalError => "FAUCET: Internal error",
+            Self::NodeError => "FAUCET: Node request failed",
+            Self::FaucetPaused => "FAUCET: Faucet is paused",
+            Self::FaucetOutOfFunds => "FAUCET: Faucet is out of funds",
+            Self::QueueFull => "FAUCET: Transfer queue is full",
+            Self::NodeUnavailable => "FAUCET: Node unavailable",
+            Self::RequestTimeout => "FAUCET: Request timed out",
+        }
+    }
+}
+
+/// Error returned by handlers and middleware; rendered as an
+/// [`ErrorResponse`] with the status of its [`ErrorCode`].
+#[derive(Debug)]
+pub enum ApiError {
+    InvalidRequest(String),
+    InvalidAddress(String),
+    InvalidDeployId,
+    InvalidIdempotencyKey,
+    BalanceAboveLimit,
+    Captcha(CaptchaError),
+    ProofOfWork(PowError),
+    PowDisabled,
+    Unauthorized,
+    JobNotFound(String),
+    DeployNotFound(String),
+    IdempotencyConflict(&'static str),
+    CooldownActive(Duration),
+    RateLimited(Duration),
+    /// Paused by an admin, with the maintenance message if one was set.
+    Paused(Option<String>),
+    OutOfFunds,
+    QueueFull(Duration),
+    /// A call to a validator or the observer failed.
+    Node(NodeCliError),
+    /// Submitting a transfer failed in a way that does not rule out that the
+    /// deploy reached a node.
+    Unconfirmed(NodeCliError),
+    /// The request was not done within the server's request timeout.
+    RequestTimeout(Duration),
+    /// An error stored for an `Idempotency-Key`, sent again as it was.
+    Replayed(ErrorCode, Option<String>),
+    /// Details are logged where the error occurs, not sent to the client.
+    Internal(&'static str),
+}
+
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            Self::InvalidAddress(_) => ErrorCode::InvalidAddress,
+            Self::InvalidDeployId => ErrorCode::InvalidDeployId,
+            Self::InvalidIdempotencyKey => ErrorCode::InvalidIdempotencyKey,
+            Self::BalanceAboveLimit => ErrorCode::BalanceAboveLimit,
+            Self::Captcha(CaptchaError::Missing) => ErrorCode::CaptchaRequired,
+            Self::Captcha(CaptchaError::Replayed | CaptchaError::Rejected(_)) => {
+                ErrorCode::CaptchaRejected
+            }
+            Self::Captcha(CaptchaError::Unavailable(_)) => ErrorCode::CaptchaUnavailable,
+            Self::ProofOfWork(PowError::Missing | PowError::Malformed) => ErrorCode::PowInvalid,
+            Self::ProofOfWork(_) => ErrorCode::PowRejected,
+            Self::PowDisabled => ErrorCode::PowDisabled,
+            Self::Unauthorized => ErrorCode::Unauthorized,
+            Self::JobNotFound(_) => ErrorCode::JobNotFound,
+            Self::DeployNotFound(_) => ErrorCode::DeployNotFound,
+            Self::IdempotencyConflict(_) => ErrorCode::IdempotencyConflict,
+            Self::CooldownActive(_) => ErrorCode::CooldownActive,
+            Self::RateLimited(_) => ErrorCode::RateLimited,
+            Self::Paused(_) => ErrorCode::FaucetPaused,
+            Self::OutOfFunds => ErrorCode::FaucetOutOfFunds,
+            Self::QueueFull(_) => ErrorCode::QueueFull,
+            Self::Node(e) | Self::Unconfirmed(e) => match e {
+                NodeCliError::NoAvailableNode
+                | NodeCliError::Unreachable(_)
+                | NodeCliError::Interrupted(_)
+                | NodeCliError::Timeout(_) => ErrorCode::NodeUnavailable,
+                NodeCliError::InsufficientFunds(_) => ErrorCode::FaucetOutOfFunds,
+                NodeCliError::InvalidAddress(_) => ErrorCode::InvalidAddress,
+                NodeCliError::NotFound(_) | NodeCliError::Other(_) => ErrorCode::NodeError,
+            },
+            Self::RequestTimeout(_) => ErrorCode::RequestTimeout,
+            Self::Replayed(code, _) => *code,
+            Self::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    pub fn details(&self) -> Option<String> {
+        match self {
+            Self::InvalidRequest(message) | Self::InvalidAddress(message) => Some(message.clone()),
+            Self::InvalidDeployId => {
+                Some("Deploy ID must be 100-160 alphanumeric characters".to_string())
+            }
+            Self::InvalidIdempotencyKey => {
+                Some("Idempotency-Key must be 1-255 visible ASCII characters".to_string())
+            }
+            Self::BalanceAboveLimit => {
+                Some("Address balance exceeds faucet eligibility threshold".to_string())
+            }
+            Self::Captcha(e) => Some(e.to_string()),
+            Self::ProofOfWork(e) => Some(e.to_string()),
+            Self::PowDisabled => None,
+            Self::Unauthorized => {
+                Some("A valid admin bearer token or client certificate is required".to_string())
+            }
+            Self::JobNotFound(id) => Some(format!("No transfer job with id {}", id)),
+            Self::DeployNotFound(id) => Some(format!("No deploy with id {}", id)),
+            Self::IdempotencyConflict(details) | Self::Internal(details) => {
+                Some(details.to_string())
+            }
+            Self::CooldownActive(remaining) => Some(format!(
+                "Address can request tokens again in {} seconds",
+                remaining.as_secs()
+            )),
+            Self::RateLimited(reset) => Some(format!("Try again in {} seconds", reset.as_secs())),
+            Self::Paused(message) => Some(
+                message
+                    .clone()
+                    .unwrap_or_else(|| "The faucet is temporarily unavailable".to_string()),
+            ),
+            Self::OutOfFunds => {
+                Some("Transfers are paused until the faucet wallet is topped up".to_string())
+            }
+            Self::Node(NodeCliError::InsufficientFunds(_)) => {
+                Some("The faucet wallet cannot cover this transfer".to_string())
+            }
+            Self::QueueFull(_) => Some("Too many pending transfers, try again later".to_string()),
+            Self::Node(e) => Some(e.to_string()),
+            Self::Unconfirmed(e) => Some(format!(
+                "{}. The transfer may still be deployed; check the recipient's balance before retrying",
+                e
+            )),
+            Self::RequestTimeout(timeout) => Some(format!(
+                "The request did not complete within {} seconds",
+                timeout.as_secs()
+            )),
+            Self::Replayed(_, details) => details.clone(),
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::CooldownActive(wait) | Self::RateLimited(wait) | Self::QueueFull(wait) => {
+                Some(*wait)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<NodeCliError> for ApiError {
+    fn from(e: NodeCliError) -> Self {
+        Self::Node(e)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::InvalidRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let code = self.code();
+        let mut response = (
+            code.status(),
+            Json(ErrorResponse::new(code, self.details())),
+        )
+            .into_response();
+
+        if let Some(wait) = self.retry_after() {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(wait.as_secs()));
+        }
+        response
+    }
+}
+
+/// `Json` extractor whose rejections are reported as `INVALID_REQUEST`.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct JsonBody<T>(pub T);
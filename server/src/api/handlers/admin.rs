@@ -0,0 +1,197 @@
+use crate::{
+    api::error::{ApiError, JsonBody},
+    api::models::{
+        AdminSettingsResponse, AdminWalletsResponse, ApiResult, ClearCooldownResponse,
+        ErrorResponse, PauseRequest, UpdateSettingsRequest,
+    },
+    services::settings::RuntimeSettings,
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    Json as RequestJson,
+};
+use node_cli::commands::validate_address;
+use tracing::{error, info};
+
+fn settings_response(state: &AppState, settings: &RuntimeSettings) -> AdminSettingsResponse {
+    AdminSettingsResponse {
+        paused: settings.paused,
+        maintenance_message: settings.maintenance_message.clone(),
+        low_balance: settings.low_balance,
+        faucet_amount: settings.faucet_amount,
+        faucet_max_balance: settings.faucet_max_balance,
+        faucet_cooldown_sec: state.config.faucet_cooldown_sec,
+    }
+}
+
+/// Current runtime settings.
+#[utoipa::path(
+    get,
+    path = "/admin/settings",
+    tag = "admin",
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = AdminSettingsResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_settings_handler(
+    State(state): State<AppState>,
+) -> ApiResult<AdminSettingsResponse> {
+    let settings = state.settings.load();
+    Ok(Json(settings_response(&state, &settings)))
+}
+
+/// Changes runtime settings.
+#[utoipa::path(
+    patch,
+    path = "/admin/settings",
+    tag = "admin",
+    request_body = UpdateSettingsRequest,
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = AdminSettingsResponse),
+        (status = 400, description = "Invalid settings", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_update_settings_handler(
+    State(state): State<AppState>,
+    JsonBody(request): JsonBody<UpdateSettingsRequest>,
+) -> ApiResult<AdminSettingsResponse> {
+    if request.faucet_amount == Some(0) {
+        return Err(ApiError::InvalidRequest(
+            "faucet_amount must be greater than 0".to_string(),
+        ));
+    }
+
+    let settings = state.settings.update(|settings| {
+        if let Some(amount) = request.faucet_amount {
+            settings.faucet_amount = amount;
+        }
+        if let Some(max_balance) = request.faucet_max_balance {
+            settings.faucet_max_balance = max_balance;
+        }
+        if let Some(message) = &request.maintenance_message {
+            settings.maintenance_message = Some(message.clone()).filter(|m| !m.is_empty());
+        }
+    });
+
+    info!(
+        "FAUCET: Admin updated settings: amount={} max_balance={} maintenance_message={:?}",
+        settings.faucet_amount, settings.faucet_max_balance, settings.maintenance_message
+    );
+
+    Ok(Json(settings_response(&state, &settings)))
+}
+
+/// Pauses transfers.
+#[utoipa::path(
+    post,
+    path = "/admin/pause",
+    tag = "admin",
+    request_body(content = Option<PauseRequest>, description = "Optional maintenance message"),
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = AdminSettingsResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_pause_handler(
+    State(state): State<AppState>,
+    request: Option<RequestJson<PauseRequest>>,
+) -> ApiResult<AdminSettingsResponse> {
+    let RequestJson(request) = request.unwrap_or_default();
+
+    let settings = state.settings.update(|settings| {
+        settings.paused = true;
+        if let Some(message) = &request.message {
+            settings.maintenance_message = Some(message.clone()).filter(|m| !m.is_empty());
+        }
+    });
+
+    info!(
+        "FAUCET: Admin paused the faucet (message: {:?})",
+        settings.maintenance_message
+    );
+
+    Ok(Json(settings_response(&state, &settings)))
+}
+
+/// Resumes transfers and clears the maintenance message.
+#[utoipa::path(
+    post,
+    path = "/admin/resume",
+    tag = "admin",
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = AdminSettingsResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_resume_handler(
+    State(state): State<AppState>,
+) -> ApiResult<AdminSettingsResponse> {
+    let settings = state.settings.update(|settings| {
+        settings.paused = false;
+        settings.maintenance_message = None;
+    });
+
+    info!("FAUCET: Admin resumed the faucet");
+
+    Ok(Json(settings_response(&state, &settings)))
+}
+
+/// Balance and eligibility of each faucet wallet.
+#[utoipa::path(
+    get,
+    path = "/admin/wallets",
+    tag = "admin",
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = AdminWalletsResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_wallets_handler(
+    State(state): State<AppState>,
+) -> ApiResult<AdminWalletsResponse> {
+    Ok(Json(AdminWalletsResponse {
+        wallets: state.wallets.status(),
+    }))
+}
+
+/// Lets an address receive a transfer again before its cooldown ends.
+#[utoipa::path(
+    delete,
+    path = "/admin/cooldowns/{address}",
+    tag = "admin",
+    params(("address" = String, Path, description = "ASI address")),
+    security(("admin_token" = []), ("admin_client_cert" = [])),
+    responses(
+        (status = 200, description = "OK", body = ClearCooldownResponse),
+        (status = 400, description = "Invalid address", body = ErrorResponse),
+        (status = 500, description = "Ledger error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn admin_clear_cooldown_handler(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> ApiResult<ClearCooldownResponse> {
+    validate_address(&address).map_err(|e| ApiError::InvalidAddress(e.to_string()))?;
+
+    let cooldown_was_active = state.ledger.clear_cooldown(&address).await.map_err(|e| {
+        error!("FAUCET: Failed to clear cooldown for {}: {}", address, e);
+        ApiError::Internal("Unable to clear address cooldown")
+    })?;
+
+    info!("FAUCET: Admin cleared cooldown for {}", address);
+
+    Ok(Json(ClearCooldownResponse {
+        address,
+        cooldown_was_active,
+    }))
+}
@@ -1,31 +1,38 @@
 use crate::{
+    api::error::ApiError,
     api::models::{ApiResult, BalanceResponse, ErrorResponse},
-    services::node_cli::NodeCliService,
     AppState,
 };
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     response::Json,
 };
 use node_cli::commands::validate_address;
 use tracing::{error, info};
 
+/// Balance of an address, in the smallest token unit.
+#[utoipa::path(
+    get,
+    path = "/balance/{address}",
+    tag = "faucet",
+    params(("address" = String, Path, description = "ASI address")),
+    responses(
+        (status = 200, description = "Balance returned", body = BalanceResponse),
+        (status = 400, description = "Invalid address", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+        (status = 502, description = "Node returned an error", body = ErrorResponse),
+        (status = 503, description = "No node available", body = ErrorResponse),
+    )
+)]
 pub async fn balance_handler(
     State(state): State<AppState>,
     Path(address): Path<String>,
 ) -> ApiResult<BalanceResponse> {
     info!("FAUCET: Balance request received for address: {}", address);
 
-    validate_address(&address).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::validation_error(e.to_string().as_str())),
-        )
-    })?;
+    validate_address(&address).map_err(|e| ApiError::InvalidAddress(e.to_string()))?;
 
-    let node_cli_service = NodeCliService::new(state.config.clone());
-    match node_cli_service.get_balance(&address).await {
+    match state.node_cli.get_balance(&address).await {
         Ok(balance) => {
             info!(
                 "FAUCET: Balance retrieval successful for {}: {}",
@@ -35,13 +42,7 @@ pub async fn balance_handler(
         }
         Err(e) => {
             error!("FAUCET: Balance retrieval failed: {}", e);
-            Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new(
-                    "FAUCET: Balance retrieval failed".to_string(),
-                    Some(e.to_string()),
-                )),
-            ))
+            Err(e.into())
         }
     }
 }
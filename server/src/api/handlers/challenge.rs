@@ -0,0 +1,35 @@
+use crate::{
+    api::error::ApiError,
+    api::models::{ApiResult, ChallengeResponse, ErrorResponse},
+    AppState,
+};
+use axum::{extract::State, response::Json};
+use chrono::DateTime;
+
+/// Proof-of-work challenge to solve before `POST /transfer`.
+#[utoipa::path(
+    get,
+    path = "/challenge",
+    tag = "faucet",
+    responses(
+        (status = 200, description = "Challenge issued", body = ChallengeResponse),
+        (status = 404, description = "Proof of work is not enabled", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn challenge_handler(State(state): State<AppState>) -> ApiResult<ChallengeResponse> {
+    let Some(pow) = &state.pow else {
+        return Err(ApiError::PowDisabled);
+    };
+
+    let challenge = pow.issue();
+
+    Ok(Json(ChallengeResponse {
+        challenge: challenge.challenge,
+        algorithm: "sha256".to_string(),
+        difficulty: challenge.difficulty,
+        expires_at: DateTime::from_timestamp(challenge.expires_at, 0)
+            .unwrap_or_default()
+            .to_rfc3339(),
+    }))
+}
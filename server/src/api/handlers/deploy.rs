@@ -1,34 +1,42 @@
 use crate::{
+    api::error::ApiError,
     api::models::{ApiResult, ErrorResponse},
-    services::node_cli::NodeCliService,
+    services::node_cli::NodeCliError,
     utils::validate_deploy_id,
     AppState,
 };
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     response::Json,
 };
 use node_cli::utils::output::DeployCompressedInfo;
 use tracing::{error, info};
 
+/// Status of a deploy, as reported by the observer node.
+#[utoipa::path(
+    get,
+    path = "/deploy/{deploy_id}",
+    tag = "faucet",
+    params(("deploy_id" = String, Path, description = "Deploy ID returned by `POST /transfer`")),
+    responses(
+        (status = 200, description = "Deploy info returned", body = Object),
+        (status = 400, description = "Invalid deploy ID", body = ErrorResponse),
+        (status = 404, description = "Deploy not found", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+        (status = 502, description = "Node returned an error", body = ErrorResponse),
+        (status = 503, description = "No node available", body = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
 pub async fn deploy_info_handler(
     State(state): State<AppState>,
     Path(deploy_id): Path<String>,
 ) -> ApiResult<DeployCompressedInfo> {
-    let node_cli_service = NodeCliService::new(state.config.clone());
-
     if !validate_deploy_id(&deploy_id) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::validation_error(
-                "FAUCET: Invalid deploy ID format (must be 100-160 alphanumeric chars)",
-            )),
-        ));
+        return Err(ApiError::InvalidDeployId);
     }
 
-    match node_cli_service.get_deploy_info(deploy_id.clone()).await {
+    match state.node_cli.get_deploy_info(deploy_id.clone()).await {
         Ok(deploy_info) => {
             info!(
                 "FAUCET: Deploy info retrieved successfully for ID: {}",
@@ -36,14 +44,10 @@ pub async fn deploy_info_handler(
             );
             Ok(Json(deploy_info))
         }
+        Err(NodeCliError::NotFound(_)) => Err(ApiError::DeployNotFound(deploy_id)),
         Err(e) => {
             error!("FAUCET: Failed to retrieve deploy info: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::internal_error(
-                    "FAUCET: Failed to retrieve deploy info",
-                )),
-            ))
+            Err(e.into())
         }
     }
 }
@@ -0,0 +1,83 @@
+use crate::{
+    api::models::{HealthResponse, ReadinessResponse},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+
+/// Liveness: the process is up and serving requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "status",
+    responses((status = 200, description = "Server is alive", body = HealthResponse))
+)]
+pub async fn healthz_handler(State(state): State<AppState>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        uptime_sec: state.start_time.elapsed().as_secs(),
+    })
+}
+
+/// Readiness: a transfer could be served right now. Answers 503 with the
+/// reasons when it could not.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "status",
+    responses(
+        (status = 200, description = "Ready", body = ReadinessResponse),
+        (status = 503, description = "Not ready; see `reasons`", body = ReadinessResponse),
+    )
+)]
+pub async fn readyz_handler(State(state): State<AppState>) -> Response {
+    let breakers = state.node_cli.circuit_breakers();
+    let available_nodes = state
+        .node_health
+        .healthy_nodes()
+        .iter()
+        .filter(|health| breakers.is_available(&health.node))
+        .count();
+    let observer_up = state.node_health.observer().up;
+    let settings = state.settings.load();
+    let shutting_down = state.shutdown.is_requested();
+
+    let mut reasons = Vec::new();
+    if shutting_down {
+        reasons.push("Faucet is shutting down".to_string());
+    }
+    if available_nodes == 0 {
+        reasons.push("No validator node is reachable".to_string());
+    }
+    if !observer_up {
+        reasons.push("Observer node is down".to_string());
+    }
+    if settings.low_balance {
+        reasons.push("Faucet wallet balance is below the critical threshold".to_string());
+    }
+    if settings.paused {
+        reasons.push("Faucet is paused".to_string());
+    }
+
+    let ready = reasons.is_empty();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = ReadinessResponse {
+        ready,
+        reasons,
+        available_nodes,
+        observer_up,
+        low_balance: settings.low_balance,
+        paused: settings.paused,
+        shutting_down,
+    };
+
+    (status, Json(body)).into_response()
+}
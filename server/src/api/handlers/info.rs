@@ -0,0 +1,49 @@
+use crate::{
+    api::models::{ApiResult, ErrorResponse, InfoResponse, TokenAmount},
+    utils::{format_token_amount, TOKEN_DECIMALS, TOKEN_SYMBOL},
+    AppState,
+};
+use axum::{extract::State, response::Json};
+
+fn token_amount(raw: u128) -> TokenAmount {
+    TokenAmount {
+        raw: raw.to_string(),
+        decimal: format_token_amount(raw),
+    }
+}
+
+/// Token, transfer amount, eligibility limit and faucet status.
+#[utoipa::path(
+    get,
+    path = "/info",
+    tag = "faucet",
+    responses(
+        (status = 200, description = "Info returned", body = InfoResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn info_handler(State(state): State<AppState>) -> ApiResult<InfoResponse> {
+    let settings = state.settings.load();
+
+    let wallet_addresses = state
+        .wallets
+        .status()
+        .into_iter()
+        .map(|wallet| wallet.address)
+        .collect();
+
+    Ok(Json(InfoResponse {
+        token_symbol: TOKEN_SYMBOL.to_string(),
+        token_decimals: TOKEN_DECIMALS,
+        network: state.config.network_name.clone(),
+        faucet_amount: token_amount(settings.faucet_amount as u128),
+        // The eligibility limit is configured in whole tokens.
+        faucet_max_balance: token_amount(
+            settings.faucet_max_balance as u128 * 10u128.pow(TOKEN_DECIMALS),
+        ),
+        cooldown_sec: state.config.faucet_cooldown_sec,
+        wallet_addresses,
+        paused: settings.paused || settings.low_balance,
+        maintenance_message: settings.maintenance_message.clone(),
+    }))
+}
@@ -0,0 +1,38 @@
+use crate::{
+    api::error::ApiError,
+    api::models::{ApiResult, ErrorResponse, JobResponse},
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+
+/// State of a transfer queued with `Prefer: respond-async`.
+#[utoipa::path(
+    get,
+    path = "/jobs/{job_id}",
+    tag = "faucet",
+    params(("job_id" = String, Path, description = "Job ID returned by `POST /transfer`")),
+    responses(
+        (status = 200, description = "Job found", body = JobResponse),
+        (status = 404, description = "No job with this ID", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn job_status_handler(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> ApiResult<JobResponse> {
+    let Some(job) = state.jobs.get(&job_id) else {
+        return Err(ApiError::JobNotFound(job_id));
+    };
+
+    Ok(Json(JobResponse {
+        id: job.id,
+        to_address: job.to_address,
+        status: job.status,
+        created_at: job.created_at.to_rfc3339(),
+        updated_at: job.updated_at.to_rfc3339(),
+    }))
+}
@@ -0,0 +1,35 @@
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::error;
+
+/// Prometheus text exposition format.
+static CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus metrics.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "status",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+    )
+)]
+pub async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let rendered = state.metrics.render(
+        &state.node_health.snapshot(),
+        state.node_cli.circuit_breakers(),
+        &state.wallets.status(),
+    );
+
+    match rendered {
+        Ok(body) => ([(CONTENT_TYPE, CONTENT_TYPE_TEXT)], body).into_response(),
+        Err(e) => {
+            error!("FAUCET: Failed to render metrics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
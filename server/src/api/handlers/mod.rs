@@ -1,7 +1,26 @@
+pub mod admin;
 pub mod balance;
+pub mod challenge;
 pub mod deploy;
+pub mod health;
+pub mod info;
+pub mod jobs;
+pub mod metrics;
+pub mod nodes;
+pub mod openapi;
 pub mod transfer;
 
+pub use admin::{
+    admin_clear_cooldown_handler, admin_pause_handler, admin_resume_handler,
+    admin_settings_handler, admin_update_settings_handler, admin_wallets_handler,
+};
 pub use balance::balance_handler;
+pub use challenge::challenge_handler;
 pub use deploy::deploy_info_handler;
+pub use health::{healthz_handler, readyz_handler};
+pub use info::info_handler;
+pub use jobs::job_status_handler;
+pub use metrics::metrics_handler;
+pub use nodes::nodes_status_handler;
+pub use openapi::openapi_handler;
 pub use transfer::transfer_handler;
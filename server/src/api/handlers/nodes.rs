@@ -0,0 +1,38 @@
+use crate::{
+    api::models::{ApiResult, ErrorResponse, NodeStatus, NodesStatusResponse},
+    AppState,
+};
+use axum::{extract::State, response::Json};
+
+/// Validator health and circuit breaker states.
+#[utoipa::path(
+    get,
+    path = "/nodes",
+    tag = "status",
+    responses(
+        (status = 200, description = "Status returned", body = NodesStatusResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn nodes_status_handler(State(state): State<AppState>) -> ApiResult<NodesStatusResponse> {
+    let breakers = state.node_cli.circuit_breakers();
+
+    let nodes = state
+        .node_health
+        .snapshot()
+        .into_iter()
+        .map(|health| NodeStatus {
+            circuit_breaker: breakers.state(&health.node),
+            weight: health.node.weight(),
+            up: health.up,
+            latency_ms: health.last_latency.map(|d| d.as_millis() as u64),
+            consecutive_failures: health.consecutive_failures,
+            last_checked: health.last_checked.map(|t| t.to_rfc3339()),
+            host: health.node.host,
+            grpc_port: health.node.grpc_port,
+            http_port: health.node.http_port,
+        })
+        .collect();
+
+    Ok(Json(NodesStatusResponse { nodes }))
+}
@@ -0,0 +1,7 @@
+use crate::api::openapi::ApiDoc;
+use axum::response::Json;
+use utoipa::OpenApi;
+
+pub async fn openapi_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
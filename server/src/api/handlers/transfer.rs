@@ -1,17 +1,36 @@
 use crate::{
+    api::error::{ApiError, ErrorCode, JsonBody},
     api::handlers::balance_handler,
-    api::models::{ApiResult, BalanceResponse, ErrorResponse, TransferRequest, TransferResponse},
-    services::node_cli::NodeCliService,
+    api::models::{BalanceResponse, ErrorResponse, TransferRequest, TransferResponse},
+    services::{
+        captcha::CaptchaError,
+        idempotency::IdempotencyOutcome,
+        ledger::{CooldownReservation, ReserveOutcome},
+        metrics::Rejection,
+        node_cli::NodeCliError,
+        transfer::TransferError,
+    },
+    utils::TOKEN_DECIMALS,
     AppState,
 };
-use axum::{extract::State, http::StatusCode, response::Json, Json as RequestJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
 use node_cli::commands::validate_address;
-use tracing::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{error, info, warn, Instrument};
+
+static IDEMPOTENCY_KEY: &str = "idempotency-key";
+static PREFER: &str = "prefer";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 async fn ensure_recipient_balance_below_limit(
     state: &AppState,
     address: &str,
-) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+) -> Result<(), ApiError> {
     let balance_json = balance_handler(
         State(state.clone()),
         axum::extract::Path(address.to_string()),
@@ -25,79 +44,357 @@ async fn ensure_recipient_balance_below_limit(
             "FAUCET: Unable to parse balance '{}' for address {}",
             balance, address
         );
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::validation_error(
-                "FAUCET: Unable to parse existing balance for address",
-            )),
-        )
+        NodeCliError::Other(format!("Unexpected balance value '{}'", balance))
     })?;
 
-    let max_balance_allowed: u128 = state.config.faucet_max_balance as u128 * 10u128.pow(8);
+    let max_balance_allowed: u128 =
+        state.settings.load().faucet_max_balance as u128 * 10u128.pow(TOKEN_DECIMALS);
     if balance_value >= max_balance_allowed {
         warn!(
             "FAUCET: Address {} balance {} exceeds faucet limit {}",
             address, balance_value, max_balance_allowed
         );
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::validation_error(
-                "Address balance exceeds faucet eligibility threshold",
-            )),
-        ));
+        state.metrics.record_rejection(Rejection::OverBalanceLimit);
+        return Err(ApiError::BalanceAboveLimit);
     }
 
     Ok(())
 }
 
+fn ensure_not_paused(state: &AppState) -> Result<(), ApiError> {
+    let settings = state.settings.load();
+    if settings.low_balance && !settings.paused {
+        state.metrics.record_rejection(Rejection::LowBalance);
+        return Err(ApiError::OutOfFunds);
+    }
+    if !settings.paused {
+        return Ok(());
+    }
+    state.metrics.record_rejection(Rejection::Paused);
+
+    Err(ApiError::Paused(settings.maintenance_message.clone()))
+}
+
+/// Takes the address's cooldown for this request, turning away any other
+/// request for it until the transfer is settled.
+async fn reserve_cooldown(
+    state: &AppState,
+    address: &str,
+) -> Result<CooldownReservation, ApiError> {
+    let outcome = state.ledger.reserve(address).await.map_err(|e| {
+        error!("FAUCET: Cooldown reservation failed for {}: {}", address, e);
+        ApiError::Internal("Unable to check address cooldown")
+    })?;
+
+    match outcome {
+        ReserveOutcome::Reserved(reservation) => Ok(reservation),
+        ReserveOutcome::CoolingDown(remaining) => Err(cooldown_active(state, address, remaining)),
+    }
+}
+
+fn cooldown_active(state: &AppState, address: &str, remaining: Duration) -> ApiError {
+    warn!(
+        "FAUCET: Address {} is still in cooldown for {}s",
+        address,
+        remaining.as_secs()
+    );
+    state.metrics.record_rejection(Rejection::Cooldown);
+    ApiError::CooldownActive(remaining)
+}
+
+async fn ensure_captcha_solved(state: &AppState, token: Option<&str>) -> Result<(), ApiError> {
+    state.captcha.check(token).await.map_err(|e| {
+        warn!("FAUCET: CAPTCHA check failed: {}", e);
+        if !matches!(e, CaptchaError::Unavailable(_)) {
+            state.metrics.record_rejection(Rejection::Captcha);
+        }
+        ApiError::Captcha(e)
+    })
+}
+
+fn ensure_work_proven(state: &AppState, request: &TransferRequest) -> Result<(), ApiError> {
+    let Some(pow) = &state.pow else {
+        return Ok(());
+    };
+
+    pow.verify(
+        request.pow_challenge.as_deref(),
+        request.pow_nonce.as_deref(),
+        &request.to_address,
+    )
+    .map_err(|e| {
+        warn!("FAUCET: Proof-of-work check failed: {}", e);
+        state.metrics.record_rejection(Rejection::ProofOfWork);
+        ApiError::ProofOfWork(e)
+    })
+}
+
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+            Ok(Some(key.to_owned()))
+        }
+        _ => Err(ApiError::InvalidIdempotencyKey),
+    }
+}
+
+/// What is stored for an `Idempotency-Key`: the response of a transfer that
+/// was deployed or queued, or the error of one whose outcome is unknown.
+/// Older entries hold a bare `TransferResponse`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredOutcome {
+    Failed {
+        code: ErrorCode,
+        details: Option<String>,
+    },
+    Transferred(TransferResponse),
+}
+
+fn idempotency_store_error(e: anyhow::Error) -> ApiError {
+    error!("FAUCET: Idempotency store failed: {}", e);
+    ApiError::Internal("Unable to process Idempotency-Key")
+}
+
+/// `Prefer: respond-async` (RFC 7240) asks for the transfer to be queued.
+fn prefers_async(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(PREFER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|pref| pref.trim().eq_ignore_ascii_case("respond-async"))
+}
+
+/// Queued transfers are answered with 202 Accepted.
+fn with_status(response: Json<TransferResponse>) -> (StatusCode, Json<TransferResponse>) {
+    let status = if response.job_id.is_some() {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
+    };
+    (status, response)
+}
+
+/// Sends `FAUCET_AMOUNT` to `to_address`.
+#[utoipa::path(
+    post,
+    path = "/transfer",
+    tag = "faucet",
+    request_body = TransferRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Makes retries of this request return the first response instead of sending again"),
+        ("Prefer" = Option<String>, Header, description = "`respond-async` queues the transfer and answers 202 with a job ID"),
+    ),
+    responses(
+        (status = 200, description = "Transfer deployed", body = TransferResponse),
+        (status = 202, description = "Transfer queued; poll `GET /jobs/{job_id}`", body = TransferResponse),
+        (status = 400, description = "Invalid body, address or Idempotency-Key, balance above the limit, or missing proof-of-work or CAPTCHA", body = ErrorResponse),
+        (status = 403, description = "Proof-of-work or CAPTCHA rejected", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key in progress or reused with a different body", body = ErrorResponse),
+        (status = 429, description = "Address cooldown or rate limit", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait"))),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+        (status = 502, description = "Node returned an error", body = ErrorResponse),
+        (status = 503, description = "Paused, out of funds, queue full or no node available", body = ErrorResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait, when the queue is full"))),
+    )
+)]
 #[axum::debug_handler]
 pub async fn transfer_handler(
     State(state): State<AppState>,
-    RequestJson(request): RequestJson<TransferRequest>,
-) -> ApiResult<TransferResponse> {
+    headers: HeaderMap,
+    JsonBody(request): JsonBody<TransferRequest>,
+) -> Result<(StatusCode, Json<TransferResponse>), ApiError> {
     info!(
         "FAUCET: Transfer request received for address: {}",
         request.to_address
     );
 
-    validate_address(&request.to_address).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::validation_error(e.to_string().as_str())),
-        )
-    })?;
+    // Runs on its own task so that a request timeout or a client disconnect
+    // cannot stop it between submitting the deploy and settling the
+    // Idempotency-Key and the cooldown.
+    tokio::spawn(handle_transfer(state, headers, request).in_current_span())
+        .await
+        .unwrap_or_else(|e| {
+            error!("FAUCET: Transfer task failed: {}", e);
+            Err(ApiError::Internal("Transfer failed unexpectedly"))
+        })
+}
 
-    ensure_recipient_balance_below_limit(&state, &request.to_address).await?;
+async fn handle_transfer(
+    state: AppState,
+    headers: HeaderMap,
+    request: TransferRequest,
+) -> Result<(StatusCode, Json<TransferResponse>), ApiError> {
+    let run_async = prefers_async(&headers);
 
-    let private_key = state.config.private_key.clone().unwrap();
+    let Some(key) = idempotency_key(&headers)? else {
+        return process_transfer(&state, &request, run_async)
+            .await
+            .map(with_status);
+    };
 
-    let node_cli_service = NodeCliService::new(state.config.clone());
-    match node_cli_service
-        .transfer_funds(&request.to_address, private_key)
+    let fingerprint = request.fingerprint();
+    match state
+        .idempotency
+        .begin(&key, &fingerprint)
         .await
+        .map_err(idempotency_store_error)?
     {
-        Ok(deploy_id) => {
+        IdempotencyOutcome::New => {}
+        IdempotencyOutcome::Replay(stored) => {
             info!(
-                "FAUCET: Transfer to {} deployed with id {}",
-                &request.to_address, deploy_id
+                "FAUCET: Replaying transfer response for Idempotency-Key {}",
+                key
             );
+            return match serde_json::from_str(&stored) {
+                Ok(StoredOutcome::Transferred(response)) => Ok(with_status(Json(response))),
+                Ok(StoredOutcome::Failed { code, details }) => {
+                    Err(ApiError::Replayed(code, details))
+                }
+                Err(e) => Err(idempotency_store_error(e.into())),
+            };
+        }
+        IdempotencyOutcome::InProgress => {
+            return Err(ApiError::IdempotencyConflict(
+                "A request with this Idempotency-Key is still being processed",
+            ));
+        }
+        IdempotencyOutcome::Mismatch => {
+            return Err(ApiError::IdempotencyConflict(
+                "Idempotency-Key was already used with a different request body",
+            ));
+        }
+    }
+
+    let result = process_transfer(&state, &request, run_async).await;
 
-            Ok(Json(TransferResponse {
-                deploy_id: Some(deploy_id),
-            }))
+    // A key is only given back when the transfer certainly did not reach a
+    // node. Otherwise a retry must not send again, so the outcome is kept.
+    let outcome = match &result {
+        Ok(Json(response)) => Some(StoredOutcome::Transferred(response.clone())),
+        Err(e @ ApiError::Unconfirmed(_)) => Some(StoredOutcome::Failed {
+            code: e.code(),
+            details: e.details(),
+        }),
+        Err(_) => None,
+    };
+    let stored = match outcome {
+        Some(outcome) => match serde_json::to_string(&outcome) {
+            Ok(json) => state.idempotency.complete(&key, &json).await,
+            Err(e) => Err(e.into()),
+        },
+        None => state.idempotency.release(&key).await,
+    };
+    if let Err(e) = stored {
+        error!(
+            "FAUCET: Failed to update Idempotency-Key {} after transfer: {}",
+            key, e
+        );
+    }
+
+    result.map(with_status)
+}
+
+async fn process_transfer(
+    state: &AppState,
+    request: &TransferRequest,
+    run_async: bool,
+) -> Result<Json<TransferResponse>, ApiError> {
+    ensure_not_paused(state)?;
+
+    validate_address(&request.to_address).map_err(|e| {
+        state.metrics.record_rejection(Rejection::InvalidAddress);
+        ApiError::InvalidAddress(e.to_string())
+    })?;
+
+    ensure_work_proven(state, request)?;
+    ensure_captcha_solved(state, request.captcha_token.as_deref()).await?;
+
+    // Any error before the transfer is queued drops the reservation, which
+    // gives the address back.
+    let reservation = reserve_cooldown(state, &request.to_address).await?;
+    ensure_recipient_balance_below_limit(state, &request.to_address).await?;
+
+    if run_async {
+        let job_id = state.jobs.enqueue(reservation).map_err(|_| {
+            warn!(
+                "FAUCET: Transfer job queue full, rejecting transfer to {}",
+                request.to_address
+            );
+            state.metrics.record_rejection(Rejection::QueueFull);
+            ApiError::QueueFull(state.transfers.retry_after())
+        })?;
+
+        return Ok(Json(TransferResponse {
+            deploy_id: None,
+            job_id: Some(job_id),
+        }));
+    }
+
+    match state.transfers.try_execute(reservation).await {
+        Ok(deploy_id) => Ok(Json(TransferResponse {
+            deploy_id: Some(deploy_id),
+            job_id: None,
+        })),
+        Err(TransferError::QueueFull) => {
+            warn!(
+                "FAUCET: Submission queue full, rejecting transfer to {}",
+                request.to_address
+            );
+            state.metrics.record_rejection(Rejection::QueueFull);
+            Err(ApiError::QueueFull(state.transfers.retry_after()))
         }
-        Err(e) => {
+        Err(TransferError::Node(e)) => {
             error!(
                 "FAUCET: Transfer failed to {} with error {}",
                 request.to_address, e
             );
-            Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new(
-                    "FAUCET: Transfer failed".to_string(),
-                    Some(e.to_string()),
-                )),
-            ))
+            if e.proves_not_submitted() {
+                Err(e.into())
+            } else {
+                Err(ApiError::Unconfirmed(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_failure_round_trips() {
+        let error = ApiError::Unconfirmed(NodeCliError::Timeout("no response".to_string()));
+        let json = serde_json::to_string(&StoredOutcome::Failed {
+            code: error.code(),
+            details: error.details(),
+        })
+        .unwrap();
+
+        match serde_json::from_str(&json).unwrap() {
+            StoredOutcome::Failed { code, details } => {
+                assert_eq!(code, ErrorCode::NodeUnavailable);
+                assert_eq!(details, error.details());
+            }
+            StoredOutcome::Transferred(_) => panic!("expected a stored failure"),
+        }
+    }
+
+    #[test]
+    fn stored_response_without_outcome_tag_is_a_transfer() {
+        let stored = r#"{"deploy_id":"abc"}"#;
+
+        match serde_json::from_str(stored).unwrap() {
+            StoredOutcome::Transferred(response) => {
+                assert_eq!(response.deploy_id.as_deref(), Some("abc"));
+            }
+            StoredOutcome::Failed { .. } => panic!("expected a stored transfer"),
         }
     }
 }
@@ -0,0 +1,220 @@
+use crate::api::error::ApiError;
+use crate::core::ClientCertificate;
+use axum::http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::Layer;
+use tracing::warn;
+
+/// Requires `Authorization: Bearer <ADMIN_TOKEN>` on every request, or a
+/// verified client certificate listed in `ADMIN_CLIENT_CERT_FINGERPRINTS`.
+#[derive(Clone)]
+pub struct AdminAuthLayer {
+    token: Option<Arc<str>>,
+    fingerprints: Arc<[String]>,
+}
+
+impl AdminAuthLayer {
+    pub fn new(token: Option<&str>, fingerprints: &[String]) -> Self {
+        Self {
+            token: token.map(Arc::from),
+            fingerprints: Arc::from(fingerprints),
+        }
+    }
+}
+
+impl<S> Layer<S> for AdminAuthLayer {
+    type Service = AdminAuthMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AdminAuthMiddleware {
+            inner,
+            token: self.token.clone(),
+            fingerprints: self.fingerprints.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminAuthMiddleware<S> {
+    inner: S,
+    token: Option<Arc<str>>,
+    fingerprints: Arc<[String]>,
+}
+
+impl<S> AdminAuthMiddleware<S> {
+    fn is_authorized(&self, request: &Request) -> bool {
+        self.has_valid_token(request) || self.has_admin_certificate(request)
+    }
+
+    fn has_valid_token(&self, request: &Request) -> bool {
+        let Some(expected) = &self.token else {
+            return false;
+        };
+        request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+    }
+
+    fn has_admin_certificate(&self, request: &Request) -> bool {
+        request
+            .extensions()
+            .get::<ClientCertificate>()
+            .is_some_and(|certificate| self.fingerprints.contains(&certificate.fingerprint))
+    }
+}
+
+impl<S> tower::Service<Request> for AdminAuthMiddleware<S>
+where
+    S: tower::Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if !self.is_authorized(&request) {
+            warn!(
+                "FAUCET: Rejected unauthorized admin request to {}",
+                request.uri().path()
+            );
+
+            let mut response = ApiError::Unauthorized.into_response();
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(request).await })
+    }
+}
+
+/// Compares without short-circuiting so response timing does not reveal how
+/// much of the token matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::core::Application;
+    use axum::{body::Body, extract::ConnectInfo, http::StatusCode, routing::get, Router};
+    use std::net::SocketAddr;
+    use tower::Service;
+
+    const TOKEN: &str = "admin-secret";
+    const FINGERPRINT: &str = "4f0a1bd5c4d2a7c3f1e29f7b0b9d54a1b7b0b26a3c6f0d2e8a1c5b9e7d3f2a10";
+
+    fn router() -> Router {
+        Router::new()
+            .route("/admin/wallets", get(|| async { "ok" }))
+            .layer(AdminAuthLayer::new(Some(TOKEN), &[FINGERPRINT.to_string()]))
+    }
+
+    fn request(authorization: Option<&str>, fingerprint: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri("/admin/wallets");
+        if let Some(value) = authorization {
+            builder = builder.header(AUTHORIZATION, value);
+        }
+        let mut request = builder.body(Body::empty()).unwrap();
+        if let Some(fingerprint) = fingerprint {
+            request.extensions_mut().insert(ClientCertificate {
+                fingerprint: fingerprint.to_string(),
+            });
+        }
+        request
+    }
+
+    async fn status(request: Request) -> StatusCode {
+        router().call(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn rejects_a_missing_token() {
+        let response = router().call(request(None, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_wrong_token() {
+        let wrong = format!("Bearer {}x", TOKEN);
+        assert_eq!(
+            status(request(Some(&wrong), None)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        let unprefixed = request(Some(TOKEN), None);
+        assert_eq!(status(unprefixed).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn accepts_the_token() {
+        let bearer = format!("Bearer {}", TOKEN);
+        assert_eq!(status(request(Some(&bearer), None)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn accepts_a_listed_certificate_fingerprint() {
+        assert_eq!(
+            status(request(None, Some(FINGERPRINT))).await,
+            StatusCode::OK
+        );
+        let other = "0".repeat(64);
+        assert_eq!(
+            status(request(None, Some(&other))).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn read_traffic_does_not_use_up_the_admin_rate_limit() {
+        let config =
+            AppConfig::for_tests(&[("ADMIN_TOKEN", TOKEN), ("RATE_LIMIT_READ_REQUESTS", "1")]);
+        let app = Application::build(config).await.unwrap();
+        let mut router = app.router().clone();
+        let peer = ConnectInfo("203.0.113.7:40000".parse::<SocketAddr>().unwrap());
+
+        let mut get = |uri: &str, authorization: Option<String>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(value) = authorization {
+                request = request.header(AUTHORIZATION, value);
+            }
+            let mut request = request.body(Body::empty()).unwrap();
+            request.extensions_mut().insert(peer);
+            router.call(request)
+        };
+
+        assert_ne!(
+            get("/info", None).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            get("/info", None).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        let bearer = Some(format!("Bearer {}", TOKEN));
+        assert_eq!(
+            get("/admin/wallets", bearer).await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+}
@@ -1 +1,5 @@
+pub mod admin_auth;
+pub mod metrics;
+pub mod rate_limit;
 pub mod request_id;
+pub mod timeout;
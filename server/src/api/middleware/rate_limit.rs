@@ -0,0 +1,414 @@
+use crate::api::error::ApiError;
+use crate::services::metrics::{Metrics, Rejection};
+use arc_swap::ArcSwap;
+use axum::extract::ConnectInfo;
+use axum::http::header::{HeaderMap, HeaderName, HeaderValue, FORWARDED};
+use axum::response::IntoResponse;
+use axum::{extract::Request, response::Response};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::Layer;
+use tracing::warn;
+
+static X_FORWARDED_FOR: &str = "x-forwarded-for";
+static RATELIMIT_LIMIT: &str = "ratelimit-limit";
+static RATELIMIT_REMAINING: &str = "ratelimit-remaining";
+static RATELIMIT_RESET: &str = "ratelimit-reset";
+
+/// Windows are only pruned once the table grows past this many clients.
+const PRUNE_THRESHOLD: usize = 4096;
+
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Quota {
+    limit: u32,
+    window: Duration,
+}
+
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    reset: Duration,
+}
+
+/// Fixed-window request counter keyed by client IP.
+#[derive(Clone)]
+pub struct RateLimiter {
+    quota: Arc<ArcSwap<Quota>>,
+    trusted_proxies: Arc<Vec<IpNet>>,
+    windows: Arc<Mutex<HashMap<IpAddr, Window>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration, trusted_proxies: Vec<IpNet>) -> Self {
+        Self {
+            quota: Arc::new(ArcSwap::from_pointee(Quota { limit, window })),
+            trusted_proxies: Arc::new(trusted_proxies),
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Changes the limit for subsequent requests; running windows keep their
+    /// counts.
+    pub fn set_quota(&self, limit: u32, window: Duration) {
+        self.quota.store(Arc::new(Quota { limit, window }));
+    }
+
+    fn is_enabled(&self) -> bool {
+        let quota = self.quota.load();
+        quota.limit > 0 && !quota.window.is_zero()
+    }
+
+    fn check(&self, ip: IpAddr) -> Decision {
+        let Quota {
+            limit,
+            window: length,
+        } = **self.quota.load();
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, w| now.duration_since(w.started) < length);
+        }
+
+        let window = windows.entry(ip).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+
+        if now.duration_since(window.started) >= length {
+            window.started = now;
+            window.count = 0;
+        }
+
+        let reset = length.saturating_sub(now.duration_since(window.started));
+
+        if window.count >= limit {
+            return Decision {
+                allowed: false,
+                limit,
+                remaining: 0,
+                reset,
+            };
+        }
+
+        window.count += 1;
+        Decision {
+            allowed: true,
+            limit,
+            remaining: limit - window.count,
+            reset,
+        }
+    }
+
+    fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+
+    /// Resolves the client address. Forwarding headers are only honoured when
+    /// the direct peer is a trusted proxy; the chain is then walked from the
+    /// right, skipping trusted hops, so a client cannot spoof its address by
+    /// prepending entries.
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())?;
+
+        if !self.is_trusted(&peer) {
+            return Some(peer);
+        }
+
+        let mut chain = forwarded_for(request.headers());
+        if chain.is_empty() {
+            chain = forwarded(request.headers());
+        }
+
+        let client = chain
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted(ip))
+            .or_else(|| chain.first())
+            .copied();
+
+        Some(client.unwrap_or(peer))
+    }
+}
+
+fn parse_node(raw: &str) -> Option<IpAddr> {
+    let value = raw.trim().trim_matches('"');
+
+    if let Some(rest) = value.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+
+    value
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Vec<IpAddr> {
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_node)
+        .collect()
+}
+
+fn forwarded(headers: &HeaderMap) -> Vec<IpAddr> {
+    headers
+        .get_all(FORWARDED)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                if key.trim().eq_ignore_ascii_case("for") {
+                    parse_node(value)
+                } else {
+                    None
+                }
+            })
+        })
+        .collect()
+}
+
+fn set_header(headers: &mut HeaderMap, name: &'static str, value: u64) {
+    headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: RateLimiter,
+    metrics: Option<Metrics>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self {
+            limiter,
+            metrics: None,
+        }
+    }
+
+    /// Counts rejected requests as `rate_limited` transfer rejections.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            limiter: self.limiter.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    limiter: RateLimiter,
+    metrics: Option<Metrics>,
+}
+
+impl<S> tower::Service<Request> for RateLimitMiddleware<S>
+where
+    S: tower::Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+
+        let client = if self.limiter.is_enabled() {
+            self.limiter.client_ip(&request)
+        } else {
+            None
+        };
+
+        let Some(client) = client else {
+            return Box::pin(async move { inner.call(request).await });
+        };
+
+        let decision = self.limiter.check(client);
+        let limit = decision.limit as u64;
+        let reset = decision.reset.as_secs_f64().ceil() as u64;
+
+        if !decision.allowed {
+            warn!(
+                "FAUCET: Rate limit exceeded for {} on {}",
+                client,
+                request.uri().path()
+            );
+            if let Some(metrics) = &self.metrics {
+                metrics.record_rejection(Rejection::RateLimited);
+            }
+
+            let mut response = ApiError::RateLimited(Duration::from_secs(reset)).into_response();
+
+            let headers = response.headers_mut();
+            set_header(headers, RATELIMIT_LIMIT, limit);
+            set_header(headers, RATELIMIT_REMAINING, 0);
+            set_header(headers, RATELIMIT_RESET, reset);
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(async move {
+            let mut response = inner.call(request).await?;
+            let headers = response.headers_mut();
+            set_header(headers, RATELIMIT_LIMIT, limit);
+            set_header(headers, RATELIMIT_REMAINING, decision.remaining as u64);
+            set_header(headers, RATELIMIT_RESET, reset);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(trusted: &[&str]) -> RateLimiter {
+        let trusted = trusted.iter().map(|net| net.parse().unwrap()).collect();
+        RateLimiter::new(10, Duration::from_secs(60), trusted)
+    }
+
+    fn request(peer: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let mut request = builder.body(axum::body::Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        request
+    }
+
+    fn client_ip(limiter: &RateLimiter, request: &Request) -> String {
+        limiter.client_ip(request).unwrap().to_string()
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_from_untrusted_peers() {
+        let limiter = limiter(&["10.0.0.0/8"]);
+        let request = request("203.0.113.7", &[("x-forwarded-for", "198.51.100.1")]);
+
+        assert_eq!(client_ip(&limiter, &request), "203.0.113.7");
+    }
+
+    #[test]
+    fn picks_the_rightmost_untrusted_address() {
+        let limiter = limiter(&["10.0.0.0/8"]);
+        let request = request(
+            "10.0.0.1",
+            &[("x-forwarded-for", "198.51.100.1, 203.0.113.9, 10.0.0.2")],
+        );
+
+        assert_eq!(client_ip(&limiter, &request), "203.0.113.9");
+    }
+
+    #[test]
+    fn spoofed_left_hand_entries_are_ignored() {
+        let limiter = limiter(&["10.0.0.1/32"]);
+        // The client prepended a fake address; the proxy appended the real one.
+        let request = request("10.0.0.1", &[("x-forwarded-for", "1.2.3.4, 203.0.113.9")]);
+
+        assert_eq!(client_ip(&limiter, &request), "203.0.113.9");
+    }
+
+    #[test]
+    fn repeated_headers_form_one_chain() {
+        let limiter = limiter(&["10.0.0.1/32"]);
+        let request = request(
+            "10.0.0.1",
+            &[
+                ("x-forwarded-for", "1.2.3.4"),
+                ("x-forwarded-for", "203.0.113.9"),
+            ],
+        );
+
+        assert_eq!(client_ip(&limiter, &request), "203.0.113.9");
+    }
+
+    #[test]
+    fn all_trusted_chain_falls_back_to_the_leftmost_hop() {
+        let limiter = limiter(&["10.0.0.0/8"]);
+        let request = request("10.0.0.1", &[("x-forwarded-for", "10.1.1.1, 10.2.2.2")]);
+
+        assert_eq!(client_ip(&limiter, &request), "10.1.1.1");
+    }
+
+    #[test]
+    fn trusted_peer_without_headers_is_the_client() {
+        let limiter = limiter(&["10.0.0.0/8"]);
+        let request = request("10.0.0.1", &[]);
+
+        assert_eq!(client_ip(&limiter, &request), "10.0.0.1");
+    }
+
+    #[test]
+    fn unparseable_entries_are_skipped() {
+        let limiter = limiter(&["10.0.0.0/8"]);
+        let request = request(
+            "10.0.0.1",
+            &[("x-forwarded-for", "203.0.113.9, unknown, 10.0.0.2")],
+        );
+
+        assert_eq!(client_ip(&limiter, &request), "203.0.113.9");
+    }
+
+    #[test]
+    fn handles_ipv6_peers_and_entries() {
+        let limiter = limiter(&["fd00::/8"]);
+        let request = request(
+            "fd00::1",
+            &[("x-forwarded-for", "2001:db8::1, 2001:db8::2, fd00::2")],
+        );
+
+        assert_eq!(client_ip(&limiter, &request), "2001:db8::2");
+    }
+
+    #[test]
+    fn falls_back_to_the_forwarded_header() {
+        let limiter = limiter(&["fd00::/8"]);
+        let request = request(
+            "fd00::1",
+            &[(
+                "forwarded",
+                r#"for=198.51.100.1;proto=https, for="[2001:db8::7]:4711", for=fd00::2"#,
+            )],
+        );
+
+        assert_eq!(client_ip(&limiter, &request), "2001:db8::7");
+    }
+}
@@ -8,6 +8,22 @@ use uuid::Uuid;
 
 static X_REQUEST_ID: &str = "x-request-id";
 
+/// Longest incoming request ID that is accepted as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled by the current task, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Reuses an `x-request-id` set by an upstream proxy when it is well-formed,
+/// so its logs and ours line up; otherwise a new ID is generated. The ID is
+/// echoed in the response and available to handlers through
+/// [`current_request_id`].
 #[derive(Clone, Debug)]
 pub struct RequestIdLayer;
 
@@ -44,16 +60,19 @@ where
     }
 
     fn call(&mut self, mut request: Request) -> Self::Future {
-        let request_id = Uuid::new_v4().to_string();
-
         let header_name = HeaderName::from_static(X_REQUEST_ID);
-        let header_value = match HeaderValue::from_str(&request_id) {
-            Ok(val) => val,
-            Err(_) => {
-                let mut inner = self.inner.clone();
-                return Box::pin(async move { inner.call(request).await });
-            }
-        };
+
+        let incoming = request
+            .headers()
+            .get(&header_name)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_owned);
+        let request_id = incoming.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        // Both sources only contain visible ASCII, so this cannot fail.
+        let header_value =
+            HeaderValue::from_str(&request_id).expect("request ID is a valid header value");
 
         request
             .headers_mut()
@@ -61,10 +80,20 @@ where
 
         let mut inner = self.inner.clone();
 
-        Box::pin(async move {
+        // The inner service is called inside the scope so the layers below
+        // (including the trace span) see the ID too.
+        Box::pin(REQUEST_ID.scope(request_id, async move {
             let mut response = inner.call(request).await?;
             response.headers_mut().insert(header_name, header_value);
             Ok(response)
-        })
+        }))
     }
 }
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
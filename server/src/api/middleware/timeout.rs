@@ -0,0 +1,105 @@
+use crate::api::error::ApiError;
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::Layer;
+
+/// Answers requests that are not done within `timeout` with
+/// `REQUEST_TIMEOUT`, as a regular [`ApiError`] body. Placed inside
+/// `RequestIdLayer` so the response still carries the request ID.
+#[derive(Clone, Debug)]
+pub struct TimeoutLayer {
+    timeout: Duration,
+}
+
+impl TimeoutLayer {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = TimeoutMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutMiddleware {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TimeoutMiddleware<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S> tower::Service<Request> for TimeoutMiddleware<S>
+where
+    S: tower::Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let timeout = self.timeout;
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, response).await {
+                Ok(result) => result,
+                Err(_) => Ok(ApiError::RequestTimeout(timeout).into_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::middleware::request_id::RequestIdLayer;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::Service;
+
+    #[tokio::test]
+    async fn timed_out_request_gets_an_error_body_with_its_id() {
+        let mut router = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(TimeoutLayer::new(Duration::from_millis(50)))
+            .layer(RequestIdLayer::new());
+
+        let request = Request::builder()
+            .uri("/slow")
+            .header("x-request-id", "req-slow")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(response.headers()["x-request-id"], "req-slow");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "REQUEST_TIMEOUT");
+        assert_eq!(body["request_id"], "req-slow");
+    }
+}
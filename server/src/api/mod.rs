@@ -1,6 +1,8 @@
+pub mod error;
 pub mod handlers;
 pub mod middleware;
 pub mod models;
+pub mod openapi;
 pub mod router;
 
 pub use router::create_router;
@@ -1,47 +1,181 @@
-use axum::{http::StatusCode, response::Json};
+use crate::api::{
+    error::{ApiError, ErrorCode},
+    middleware::request_id::current_request_id,
+};
+use crate::services::{circuit_breaker::BreakerState, jobs::JobStatus, wallets::WalletStatus};
+use axum::response::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-pub type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TransferRequest {
     pub to_address: String,
+    /// Required unless `CAPTCHA_PROVIDER` is `none`.
+    #[serde(default)]
+    pub captcha_token: Option<String>,
+    /// Challenge from `GET /challenge`, required when `POW_DIFFICULTY` > 0.
+    #[serde(default)]
+    pub pow_challenge: Option<String>,
+    /// Solution to `pow_challenge`.
+    #[serde(default)]
+    pub pow_nonce: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+impl TransferRequest {
+    /// Identifies the request for Idempotency-Key reuse. The anti-abuse
+    /// fields are single-use; they are left out so a retry may carry fresh
+    /// ones.
+    pub fn fingerprint(&self) -> String {
+        serde_json::json!({ "to_address": self.to_address }).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransferResponse {
     pub deploy_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobResponse {
+    pub id: String,
+    pub to_address: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChallengeResponse {
+    pub challenge: String,
+    pub algorithm: String,
+    pub difficulty: u8,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BalanceResponse {
     pub balance: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NodeStatus {
+    pub host: String,
+    pub grpc_port: u16,
+    pub http_port: u16,
+    pub weight: u32,
+    pub up: bool,
+    pub latency_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_checked: Option<String>,
+    pub circuit_breaker: BreakerState,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NodesStatusResponse {
+    pub nodes: Vec<NodeStatus>,
+}
+
+/// An amount in the token's smallest unit and as a decimal token amount.
+/// Both are strings, since the raw value may exceed a JSON number's safe
+/// integer range.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenAmount {
+    pub raw: String,
+    pub decimal: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InfoResponse {
+    pub token_symbol: String,
+    pub token_decimals: u32,
+    pub network: Option<String>,
+    pub faucet_amount: TokenAmount,
+    /// Recipients whose balance is at or above this are turned away.
+    pub faucet_max_balance: TokenAmount,
+    pub cooldown_sec: u64,
+    pub wallet_addresses: Vec<String>,
+    /// Either paused by an admin or out of funds.
+    pub paused: bool,
+    pub maintenance_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+    pub uptime_sec: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// Why the faucet is not ready; empty when it is.
+    pub reasons: Vec<String>,
+    pub available_nodes: usize,
+    pub observer_up: bool,
+    pub low_balance: bool,
+    pub paused: bool,
+    /// SIGTERM or SIGINT was received and in-flight transfers are draining.
+    pub shutting_down: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminSettingsResponse {
+    pub paused: bool,
+    pub maintenance_message: Option<String>,
+    pub low_balance: bool,
+    pub faucet_amount: u64,
+    pub faucet_max_balance: u64,
+    pub faucet_cooldown_sec: u64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSettingsRequest {
+    pub faucet_amount: Option<u64>,
+    pub faucet_max_balance: Option<u64>,
+    /// An empty string clears the message.
+    pub maintenance_message: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PauseRequest {
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminWalletsResponse {
+    pub wallets: Vec<WalletStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClearCooldownResponse {
+    pub address: String,
+    pub cooldown_was_active: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ErrorResponse {
+    pub code: ErrorCode,
     pub error: String,
     pub details: Option<String>,
     pub timestamp: String,
+    /// Matches the `x-request-id` response header; quote it when reporting
+    /// a problem.
+    pub request_id: Option<String>,
 }
 
 impl ErrorResponse {
-    pub fn new(error: String, details: Option<String>) -> Self {
+    pub fn new(code: ErrorCode, details: Option<String>) -> Self {
         Self {
-            error,
+            code,
+            error: code.message().to_string(),
             details,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            request_id: current_request_id(),
         }
     }
-
-    pub fn validation_error(message: &str) -> Self {
-        Self::new("Validation Error".to_string(), Some(message.to_string()))
-    }
-
-    pub fn internal_error(message: &str) -> Self {
-        Self::new(
-            "Internal Server Error".to_string(),
-            Some(message.to_string()),
-        )
-    }
 }
@@ -0,0 +1,187 @@
+use crate::api::handlers;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "ASI Chain Faucet API"),
+    paths(
+        handlers::transfer::transfer_handler,
+        handlers::balance::balance_handler,
+        handlers::deploy::deploy_info_handler,
+        handlers::jobs::job_status_handler,
+        handlers::challenge::challenge_handler,
+        handlers::info::info_handler,
+        handlers::nodes::nodes_status_handler,
+        handlers::health::healthz_handler,
+        handlers::health::readyz_handler,
+        handlers::metrics::metrics_handler,
+        handlers::admin::admin_settings_handler,
+        handlers::admin::admin_update_settings_handler,
+        handlers::admin::admin_pause_handler,
+        handlers::admin::admin_resume_handler,
+        handlers::admin::admin_wallets_handler,
+        handlers::admin::admin_clear_cooldown_handler,
+    ),
+    tags(
+        (name = "faucet", description = "Transfers and chain queries"),
+        (name = "status", description = "Health, node status and metrics"),
+        (name = "admin", description = "Runtime control; only mounted when `ADMIN_TOKEN` or `ADMIN_CLIENT_CERT_FINGERPRINTS` is set"),
+    ),
+    modifiers(&AdminTokenScheme),
+)]
+pub struct ApiDoc;
+
+struct AdminTokenScheme;
+
+impl Modify for AdminTokenScheme {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "admin_client_cert",
+            SecurityScheme::MutualTls {
+                description: Some(
+                    "Client certificate listed in `ADMIN_CLIENT_CERT_FINGERPRINTS`".to_string(),
+                ),
+                extensions: None,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::router::route_paths;
+    use crate::config::AppConfig;
+    use crate::core::Application;
+    use axum::body::Body;
+    use axum::http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        Method, Request, StatusCode,
+    };
+    use std::collections::BTreeSet;
+    use tower::Service;
+
+    const METHODS: [Method; 5] = [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+    ];
+
+    /// Routes that are served but deliberately left out of the document.
+    const UNDOCUMENTED: [&str; 1] = ["GET /openapi.json"];
+
+    /// Operations in `ApiDoc`, as `METHOD /path/{param}`.
+    fn documented() -> BTreeSet<String> {
+        let mut documented = BTreeSet::new();
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            let operations = [
+                (Method::GET, &item.get),
+                (Method::POST, &item.post),
+                (Method::PUT, &item.put),
+                (Method::PATCH, &item.patch),
+                (Method::DELETE, &item.delete),
+            ];
+            for (method, operation) in operations {
+                if operation.is_some() {
+                    documented.insert(format!("{} {}", method, path));
+                }
+            }
+        }
+        documented
+    }
+
+    /// Axum's `/balance/:address` in OpenAPI syntax, `/balance/{address}`.
+    fn openapi_path(route: &str) -> String {
+        route
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{}}}", param),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// `/balance/{address}` becomes `/balance/sample`.
+    fn sample_uri(path: &str) -> String {
+        path.split('/')
+            .map(|segment| {
+                if segment.starts_with('{') && segment.ends_with('}') {
+                    "sample"
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Whether the router has a handler for the request. Handlers that
+    /// answer 404 themselves send an error body, unlike the router.
+    async fn is_routed(router: &mut axum::Router, method: &Method, path: &str) -> bool {
+        let request = Request::builder()
+            .method(method)
+            .uri(sample_uri(path))
+            .header(AUTHORIZATION, "Bearer test-token")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+
+        match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED => false,
+            StatusCode::NOT_FOUND => response.headers().contains_key(CONTENT_TYPE),
+            _ => true,
+        }
+    }
+
+    #[tokio::test]
+    async fn documented_operations_match_the_router() {
+        // Every optional route group is mounted, so the whole document is
+        // checked.
+        let config =
+            AppConfig::for_tests(&[("ADMIN_TOKEN", "test-token"), ("METRICS_ENABLED", "true")]);
+        let app = Application::build(config).await.unwrap();
+        let mut router = app.router().clone();
+
+        let mut documented = documented();
+        documented.extend(UNDOCUMENTED.map(String::from));
+
+        // Both the router's paths and the document's are probed, so a route
+        // missing on either side shows up.
+        let mut paths: BTreeSet<String> = ApiDoc::openapi().paths.paths.into_keys().collect();
+        paths.extend(route_paths().into_iter().map(openapi_path));
+
+        let mut mounted = BTreeSet::new();
+        for path in &paths {
+            for method in &METHODS {
+                if is_routed(&mut router, method, path).await {
+                    mounted.insert(format!("{} {}", method, path));
+                }
+            }
+        }
+
+        let unrouted: Vec<_> = documented.difference(&mounted).collect();
+        let undocumented: Vec<_> = mounted.difference(&documented).collect();
+        assert!(
+            unrouted.is_empty(),
+            "documented but not routed: {:?}",
+            unrouted
+        );
+        assert!(
+            undocumented.is_empty(),
+            "routed but not documented: {:?}",
+            undocumented
+        );
+    }
+}
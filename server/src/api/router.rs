@@ -1,48 +1,190 @@
 use crate::{
-    api::handlers::{balance_handler, deploy_info_handler, transfer_handler},
+    api::handlers::{
+        admin_clear_cooldown_handler, admin_pause_handler, admin_resume_handler,
+        admin_settings_handler, admin_update_settings_handler, admin_wallets_handler,
+        balance_handler, challenge_handler, deploy_info_handler, healthz_handler, info_handler,
+        job_status_handler, metrics_handler, nodes_status_handler, openapi_handler, readyz_handler,
+        transfer_handler,
+    },
+    api::middleware::admin_auth::AdminAuthLayer,
+    api::middleware::metrics::MetricsLayer,
+    api::middleware::rate_limit::RateLimitLayer,
     api::middleware::request_id::RequestIdLayer,
+    api::middleware::timeout::TimeoutLayer,
+    telemetry,
+    utils::REQUEST_TIMEOUT,
     AppState,
 };
 use axum::http::{
-    header::{AUTHORIZATION, CONTENT_TYPE},
+    header::{HeaderName, AUTHORIZATION, CONTENT_TYPE},
     Method, StatusCode,
 };
 use axum::{
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post, MethodRouter},
     Router,
 };
 use std::time::Duration;
 use tower_http::cors::Any;
 use tower_http::{
-    compression::CompressionLayer, cors::CorsLayer, limit::RequestBodyLimitLayer,
-    timeout::TimeoutLayer,
+    compression::CompressionLayer, cors::CorsLayer, limit::RequestBodyLimitLayer, trace::TraceLayer,
 };
+#[cfg(feature = "swagger-ui")]
+use utoipa_swagger_ui::SwaggerUi;
 
 async fn preflight() -> impl IntoResponse {
     StatusCode::NO_CONTENT
 }
 
+/// Paths with their handlers. `create_router` mounts these groups and the
+/// OpenAPI test reads the same tables, so a new route cannot go undocumented.
+type Routes = Vec<(&'static str, MethodRouter<AppState>)>;
+
+fn transfer_routes() -> Routes {
+    vec![("/transfer", post(transfer_handler).options(preflight))]
+}
+
+fn read_routes() -> Routes {
+    vec![
+        (
+            "/deploy/:deploy_id",
+            get(deploy_info_handler).options(preflight),
+        ),
+        ("/balance/:address", get(balance_handler).options(preflight)),
+        ("/nodes", get(nodes_status_handler).options(preflight)),
+        ("/challenge", get(challenge_handler).options(preflight)),
+        ("/jobs/:job_id", get(job_status_handler).options(preflight)),
+        ("/info", get(info_handler).options(preflight)),
+    ]
+}
+
+fn probe_routes() -> Routes {
+    vec![
+        ("/healthz", get(healthz_handler)),
+        ("/readyz", get(readyz_handler)),
+    ]
+}
+
+fn metrics_routes() -> Routes {
+    vec![("/metrics", get(metrics_handler))]
+}
+
+fn admin_routes() -> Routes {
+    vec![
+        (
+            "/admin/settings",
+            get(admin_settings_handler).patch(admin_update_settings_handler),
+        ),
+        ("/admin/pause", post(admin_pause_handler)),
+        ("/admin/resume", post(admin_resume_handler)),
+        ("/admin/wallets", get(admin_wallets_handler)),
+        (
+            "/admin/cooldowns/:address",
+            delete(admin_clear_cooldown_handler),
+        ),
+    ]
+}
+
+fn document_routes() -> Routes {
+    vec![("/openapi.json", get(openapi_handler))]
+}
+
+fn mount(routes: Routes) -> Router<AppState> {
+    routes
+        .into_iter()
+        .fold(Router::new(), |router, (path, handler)| {
+            router.route(path, handler)
+        })
+}
+
+/// Every path `create_router` can mount, whatever the configuration.
+#[cfg(test)]
+pub(crate) fn route_paths() -> Vec<&'static str> {
+    [
+        transfer_routes(),
+        read_routes(),
+        probe_routes(),
+        metrics_routes(),
+        admin_routes(),
+        document_routes(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|(path, _)| path)
+    .collect()
+}
+
 pub fn create_router(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers([CONTENT_TYPE, AUTHORIZATION])
+        .allow_headers([
+            CONTENT_TYPE,
+            AUTHORIZATION,
+            HeaderName::from_static("idempotency-key"),
+            HeaderName::from_static("prefer"),
+        ])
         .max_age(Duration::from_secs(60 * 60));
 
-    let api_routes = Router::new()
-        .route("/transfer", post(transfer_handler).options(preflight))
-        .route(
-            "/deploy/:deploy_id",
-            get(deploy_info_handler).options(preflight),
-        )
-        .route("/balance/:address", get(balance_handler).options(preflight));
+    let config = &state.config;
+
+    let transfer_routes = mount(transfer_routes()).layer(
+        RateLimitLayer::new(state.transfer_limiter.clone()).with_metrics(state.metrics.clone()),
+    );
+
+    let read_routes = mount(read_routes()).layer(RateLimitLayer::new(state.read_limiter.clone()));
+
+    // Probes are polled by orchestrators and are not rate limited.
+    let probe_routes = mount(probe_routes());
+
+    let mut api_routes = transfer_routes
+        .merge(read_routes)
+        .merge(probe_routes)
+        .merge(mount(document_routes()));
+
+    if config.metrics_enabled {
+        api_routes = api_routes.merge(mount(metrics_routes()));
+    }
+
+    // The admin API is only mounted when a token or admin client
+    // certificates are configured. The rate limit sits in front of the
+    // authentication, so failed attempts count too and the token cannot be
+    // guessed at line rate. It has its own counter so public read traffic
+    // cannot lock operators out.
+    if config.admin_token.is_some() || !config.admin_client_cert_fingerprints.is_empty() {
+        api_routes = api_routes.merge(
+            mount(admin_routes())
+                .layer(AdminAuthLayer::new(
+                    config
+                        .admin_token
+                        .as_ref()
+                        .map(|token| token.expose_secret()),
+                    &config.admin_client_cert_fingerprints,
+                ))
+                .layer(RateLimitLayer::new(state.admin_limiter.clone())),
+        );
+    }
+
+    #[cfg(feature = "swagger-ui")]
+    {
+        api_routes = api_routes.merge(
+            SwaggerUi::new("/docs").config(utoipa_swagger_ui::Config::from("/openapi.json")),
+        );
+    }
 
     api_routes
         .layer(cors)
         .layer(RequestBodyLimitLayer::new(1024 * 1024))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::request_span)
+                .on_request(())
+                .on_response(telemetry::record_response)
+                .on_failure(()),
+        )
+        .layer(TimeoutLayer::new(REQUEST_TIMEOUT))
         .layer(RequestIdLayer::new())
-        .layer(TimeoutLayer::new(Duration::from_secs(7)))
         .layer(CompressionLayer::new())
+        .layer(MetricsLayer::new(state.metrics.clone()))
         .with_state(state)
 }
@@ -1,18 +1,58 @@
 use anyhow::{Context, Result};
-use std::time::Instant;
-use tracing::info;
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-use crate::{api::create_router, config::AppConfig};
+use super::reload::ConfigReloader;
+use super::shutdown::Shutdown;
+use super::tls::TlsServer;
+use crate::{
+    api::create_router,
+    api::middleware::rate_limit::RateLimiter,
+    config::AppConfig,
+    services::{
+        balance_monitor::BalanceMonitor,
+        captcha::{new_verifier, CaptchaGuard},
+        idempotency::IdempotencyStore,
+        jobs::TransferJobs,
+        ledger::TransferLedger,
+        metrics::Metrics,
+        node_cli::NodeCliService,
+        node_health::NodeHealthMonitor,
+        pow::ProofOfWork,
+        settings::FaucetSettings,
+        transfer::TransferService,
+        wallets::WalletPool,
+    },
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: AppConfig,
     pub start_time: Instant,
+    pub settings: FaucetSettings,
+    pub ledger: TransferLedger,
+    pub idempotency: IdempotencyStore,
+    pub captcha: CaptchaGuard,
+    pub pow: Option<ProofOfWork>,
+    pub node_health: NodeHealthMonitor,
+    pub node_cli: NodeCliService,
+    pub wallets: WalletPool,
+    pub transfers: TransferService,
+    pub jobs: TransferJobs,
+    pub transfer_limiter: RateLimiter,
+    pub read_limiter: RateLimiter,
+    pub admin_limiter: RateLimiter,
+    pub metrics: Metrics,
+    pub shutdown: Shutdown,
 }
 
 pub struct Application {
     router: axum::Router,
     config: AppConfig,
+    state: AppState,
+    tls: Option<TlsServer>,
 }
 
 impl Application {
@@ -21,17 +61,123 @@ impl Application {
             .validate()
             .map_err(|e| anyhow::anyhow!("Configuration error: {}", e))?;
 
+        let settings = FaucetSettings::new(&config);
+
+        let tls = TlsServer::from_config(&config).context("Failed to load TLS certificate")?;
+
+        let ledger = TransferLedger::open(&config.ledger_db_path, config.faucet_cooldown_sec)
+            .context("Failed to open transfer ledger")?;
+        let idempotency =
+            IdempotencyStore::open(&config.ledger_db_path, config.idempotency_key_ttl_sec)
+                .context("Failed to open idempotency store")?;
+
+        let captcha = CaptchaGuard::new(
+            new_verifier(&config).context("Failed to create CAPTCHA verifier")?,
+            Duration::from_secs(config.captcha_token_ttl_sec),
+        );
+
+        let pow = ProofOfWork::from_config(&config);
+
+        let node_health = NodeHealthMonitor::new(
+            &config.node_sockets,
+            config.observer_socket(),
+            Duration::from_secs(config.node_health_check_interval_sec),
+            Duration::from_secs(config.node_health_check_timeout_sec),
+        )
+        .context("Failed to create node health monitor")?;
+        node_health.probe_all().await;
+        node_health.spawn();
+
+        let metrics = Metrics::new().context("Failed to create metrics registry")?;
+
+        let node_cli = NodeCliService::new(config.clone(), node_health.clone(), metrics.clone());
+
+        let wallets = WalletPool::new(&config, settings.clone(), node_cli.clone())
+            .context("Failed to create wallet pool")?;
+
+        let balance_monitor = BalanceMonitor::new(&config, wallets.clone(), settings.clone())
+            .context("Failed to create balance monitor")?;
+        balance_monitor.check().await;
+        balance_monitor.spawn();
+
+        let transfers = TransferService::start(
+            &config,
+            settings.clone(),
+            node_cli.clone(),
+            wallets.clone(),
+            metrics.clone(),
+        );
+        let jobs = TransferJobs::start(
+            transfers.clone(),
+            config.transfer_job_workers,
+            config.transfer_job_queue_size,
+            Duration::from_secs(config.transfer_job_retention_sec),
+        );
+
+        let transfer_limiter = RateLimiter::new(
+            config.rate_limit_transfer_requests,
+            Duration::from_secs(config.rate_limit_transfer_window_sec),
+            config.trusted_proxies.clone(),
+        );
+        let read_limiter = RateLimiter::new(
+            config.rate_limit_read_requests,
+            Duration::from_secs(config.rate_limit_read_window_sec),
+            config.trusted_proxies.clone(),
+        );
+        let admin_limiter = RateLimiter::new(
+            config.rate_limit_admin_requests,
+            Duration::from_secs(config.rate_limit_admin_window_sec),
+            config.trusted_proxies.clone(),
+        );
+
+        ConfigReloader::new(
+            config.clone(),
+            settings.clone(),
+            node_health.clone(),
+            wallets.clone(),
+            transfer_limiter.clone(),
+            read_limiter.clone(),
+            admin_limiter.clone(),
+        )
+        .spawn(Duration::from_secs(config.config_reload_interval_sec))
+        .context("Failed to start config reloader")?;
+
+        if let Some(tls) = &tls {
+            tls.spawn_reloader(Duration::from_secs(config.config_reload_interval_sec))
+                .context("Failed to start TLS certificate reloader")?;
+        }
+
         let state = AppState {
             config: config.clone(),
             start_time: Instant::now(),
+            settings,
+            ledger,
+            idempotency,
+            captcha,
+            pow,
+            node_health,
+            node_cli,
+            wallets,
+            transfers,
+            jobs,
+            transfer_limiter,
+            read_limiter,
+            admin_limiter,
+            metrics,
+            shutdown: Shutdown::new(),
         };
 
-        let router = create_router(state);
+        let router = create_router(state.clone());
 
-        Ok(Self { router, config })
+        Ok(Self {
+            router,
+            config,
+            state,
+            tls,
+        })
     }
 
-    pub async fn run(self) -> Result<()> {
+    pub async fn run(mut self) -> Result<()> {
         let addr = self.config.server_address();
         info!("Server starting on {}", addr);
 
@@ -39,15 +185,107 @@ impl Application {
             .await
             .context("Failed to bind to address")?;
 
-        info!("Listening on {}", addr);
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        info!("Listening on {}://{}", scheme, addr);
 
-        axum::serve(listener, self.router)
-            .await
-            .context("Server error")?;
+        let shutdown = self.state.shutdown.clone();
+        shutdown
+            .listen_for_signals()
+            .context("Failed to install shutdown signal handlers")?;
+
+        let signal = shutdown.clone();
+        let mut server = match self.tls.take() {
+            Some(tls) => tokio::spawn(tls.serve(listener, self.router.clone(), signal)),
+            None => tokio::spawn(
+                axum::serve(
+                    listener,
+                    self.router
+                        .clone()
+                        .into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(async move { signal.requested().await })
+                .into_future(),
+            ),
+        };
+
+        tokio::select! {
+            result = &mut server => {
+                return result.context("Server task failed")?.context("Server error");
+            }
+            _ = shutdown.requested() => {}
+        }
 
+        self.drain(server).await;
         Ok(())
     }
 
+    /// Gives open requests and queued transfers until `SHUTDOWN_TIMEOUT_SEC`
+    /// to finish, logs what is abandoned and flushes the ledger. New
+    /// connections are no longer accepted at this point.
+    async fn drain(&self, mut server: tokio::task::JoinHandle<std::io::Result<()>>) {
+        let timeout = Duration::from_secs(self.config.shutdown_timeout_sec);
+        let deadline = Instant::now() + timeout;
+        info!(
+            "FAUCET: Draining in-flight transfers for up to {}s",
+            timeout.as_secs()
+        );
+
+        let (server_result, queued_jobs) = tokio::join!(
+            tokio::time::timeout_at(deadline.into(), &mut server),
+            self.state.jobs.drain(deadline),
+        );
+        match server_result {
+            Ok(Ok(Ok(()))) => {}
+            Ok(Ok(Err(e))) => error!("FAUCET: Server error while draining: {}", e),
+            Ok(Err(e)) => error!("FAUCET: Server task failed while draining: {}", e),
+            Err(_) => {
+                warn!("FAUCET: Open requests did not finish before the shutdown deadline");
+                server.abort();
+            }
+        }
+        let pending = self.state.transfers.drain(deadline).await;
+
+        for job in &queued_jobs {
+            warn!(
+                "FAUCET: Abandoned transfer job {} to {} before submission",
+                job.id, job.to_address
+            );
+            self.release_cooldown(&job.to_address).await;
+        }
+        for transfer in &pending {
+            if transfer.deploying {
+                warn!(
+                    "FAUCET: Abandoned transfer to {} while deploying; it may still be deployed but is not recorded in the ledger, its cooldown is kept",
+                    transfer.to_address
+                );
+            } else {
+                warn!(
+                    "FAUCET: Abandoned queued transfer to {} before submission",
+                    transfer.to_address
+                );
+                self.release_cooldown(&transfer.to_address).await;
+            }
+        }
+        if queued_jobs.is_empty() && pending.is_empty() {
+            info!("FAUCET: All in-flight transfers finished");
+        }
+
+        if let Err(e) = self.state.ledger.checkpoint().await {
+            error!("FAUCET: Failed to flush the ledger: {}", e);
+        }
+    }
+
+    /// Gives the address of a transfer that was never submitted back, so it
+    /// is not locked out for a full cooldown.
+    async fn release_cooldown(&self, address: &str) {
+        if let Err(e) = self.state.ledger.release_reservations(address).await {
+            error!(
+                "FAUCET: Failed to release cooldown reservation of {}: {}",
+                address, e
+            );
+        }
+    }
+
     pub fn router(&self) -> &axum::Router {
         &self.router
     }
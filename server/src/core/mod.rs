@@ -1,3 +1,7 @@
 mod app;
+mod reload;
+mod shutdown;
+mod tls;
 
 pub use app::{AppState, Application};
+pub use tls::ClientCertificate;
@@ -0,0 +1,207 @@
+use crate::{
+    api::middleware::rate_limit::RateLimiter,
+    config::AppConfig,
+    services::{node_health::NodeHealthMonitor, settings::FaucetSettings, wallets::WalletPool},
+};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Re-reads the configuration on SIGHUP or when `CONFIG_FILE` changes, and
+/// applies the fields that can change while serving: the node list, the
+/// faucet keys, the faucet amount and max balance, and the rate limits. Each is swapped
+/// atomically, so in-flight requests finish with the values they started
+/// with.
+pub struct ConfigReloader {
+    current: AppConfig,
+    file: Option<PathBuf>,
+    settings: FaucetSettings,
+    node_health: NodeHealthMonitor,
+    wallets: WalletPool,
+    transfer_limiter: RateLimiter,
+    read_limiter: RateLimiter,
+    admin_limiter: RateLimiter,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        current: AppConfig,
+        settings: FaucetSettings,
+        node_health: NodeHealthMonitor,
+        wallets: WalletPool,
+        transfer_limiter: RateLimiter,
+        read_limiter: RateLimiter,
+        admin_limiter: RateLimiter,
+    ) -> Self {
+        let file = current.config_file.as_ref().map(PathBuf::from);
+
+        Self {
+            current,
+            file,
+            settings,
+            node_health,
+            wallets,
+            transfer_limiter,
+            read_limiter,
+            admin_limiter,
+        }
+    }
+
+    /// Watches for SIGHUP, and polls the config file's modification time
+    /// every `poll_interval` (zero disables polling).
+    pub fn spawn(mut self, poll_interval: Duration) -> anyhow::Result<JoinHandle<()>> {
+        let mut hangup = signal(SignalKind::hangup())?;
+
+        Ok(tokio::spawn(async move {
+            let polling = !poll_interval.is_zero() && self.file.is_some();
+            let mut ticker = tokio::time::interval(poll_interval.max(Duration::from_secs(1)));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut last_modified = self.modified();
+
+            loop {
+                tokio::select! {
+                    _ = hangup.recv() => {
+                        info!("FAUCET: SIGHUP received, reloading configuration");
+                        last_modified = self.modified();
+                        self.reload().await;
+                    }
+                    _ = ticker.tick(), if polling => {
+                        let modified = self.modified();
+                        if modified != last_modified {
+                            info!("FAUCET: Config file changed, reloading configuration");
+                            last_modified = modified;
+                            self.reload().await;
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        let file = self.file.as_ref()?;
+        std::fs::metadata(file).and_then(|m| m.modified()).ok()
+    }
+
+    async fn reload(&mut self) {
+        // Decrypting a keystore takes a while; keep it off the runtime.
+        let loaded = tokio::task::spawn_blocking(|| AppConfig::load().map_err(|e| e.to_string()))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+        let next = match loaded {
+            Ok(config) => config,
+            Err(e) => {
+                error!(
+                    "FAUCET: Config reload failed, keeping current settings: {}",
+                    e
+                );
+                return;
+            }
+        };
+        if let Err(e) = next.validate() {
+            error!(
+                "FAUCET: Reloaded config is invalid, keeping current settings: {}",
+                e
+            );
+            return;
+        }
+
+        let current = &self.current;
+
+        if next.node_sockets.is_empty() {
+            warn!("FAUCET: Reloaded config has no nodes, keeping the current node list");
+        } else if next.node_sockets != current.node_sockets {
+            info!(
+                "FAUCET: Node list changed ({} -> {} nodes)",
+                current.node_sockets.len(),
+                next.node_sockets.len()
+            );
+            self.node_health.set_nodes(&next.node_sockets);
+            self.node_health.probe_all().await;
+        }
+
+        if next.private_keys != current.private_keys {
+            match self.wallets.set_keys(&next.private_keys) {
+                Ok(()) => self.wallets.refresh_balances().await,
+                Err(e) => error!("FAUCET: Keeping the current faucet keys: {}", e),
+            }
+        }
+
+        // Only changed values are applied, so a reload does not undo
+        // adjustments made through the admin API.
+        if next.faucet_amount != current.faucet_amount
+            || next.faucet_max_balance != current.faucet_max_balance
+        {
+            info!(
+                "FAUCET: Faucet amount {} and max balance {} reloaded",
+                next.faucet_amount, next.faucet_max_balance
+            );
+            let (amount, max_balance) = (next.faucet_amount, next.faucet_max_balance);
+            self.settings.update(|settings| {
+                settings.faucet_amount = amount;
+                settings.faucet_max_balance = max_balance;
+            });
+        }
+
+        if next.rate_limit_transfer_requests != current.rate_limit_transfer_requests
+            || next.rate_limit_transfer_window_sec != current.rate_limit_transfer_window_sec
+        {
+            info!("FAUCET: Transfer rate limit reloaded");
+            self.transfer_limiter.set_quota(
+                next.rate_limit_transfer_requests,
+                Duration::from_secs(next.rate_limit_transfer_window_sec),
+            );
+        }
+
+        if next.rate_limit_read_requests != current.rate_limit_read_requests
+            || next.rate_limit_read_window_sec != current.rate_limit_read_window_sec
+        {
+            info!("FAUCET: Read rate limit reloaded");
+            self.read_limiter.set_quota(
+                next.rate_limit_read_requests,
+                Duration::from_secs(next.rate_limit_read_window_sec),
+            );
+        }
+
+        if next.rate_limit_admin_requests != current.rate_limit_admin_requests
+            || next.rate_limit_admin_window_sec != current.rate_limit_admin_window_sec
+        {
+            info!("FAUCET: Admin rate limit reloaded");
+            self.admin_limiter.set_quota(
+                next.rate_limit_admin_requests,
+                Duration::from_secs(next.rate_limit_admin_window_sec),
+            );
+        }
+
+        if restart_required(current, &next) {
+            warn!("FAUCET: Some changed settings only take effect after a restart");
+        }
+
+        self.current = next;
+    }
+}
+
+/// Whether anything besides the reloadable fields differs.
+fn restart_required(current: &AppConfig, next: &AppConfig) -> bool {
+    let mut comparable = next.clone();
+    comparable.node_sockets = current.node_sockets.clone();
+    comparable.private_keys = current.private_keys.clone();
+    comparable.faucet_amount = current.faucet_amount;
+    comparable.faucet_max_balance = current.faucet_max_balance;
+    comparable.rate_limit_transfer_requests = current.rate_limit_transfer_requests;
+    comparable.rate_limit_transfer_window_sec = current.rate_limit_transfer_window_sec;
+    comparable.rate_limit_read_requests = current.rate_limit_read_requests;
+    comparable.rate_limit_read_window_sec = current.rate_limit_read_window_sec;
+    comparable.rate_limit_admin_requests = current.rate_limit_admin_requests;
+    comparable.rate_limit_admin_window_sec = current.rate_limit_admin_window_sec;
+
+    // Secrets are redacted in `Debug`, so they are compared directly.
+    format!("{:?}", comparable) != format!("{:?}", current)
+        || next.captcha_secret != current.captcha_secret
+        || next.pow_secret != current.pow_secret
+        || next.admin_token != current.admin_token
+        || next.balance_alert_webhook_url != current.balance_alert_webhook_url
+        || next.otlp_endpoint != current.otlp_endpoint
+}
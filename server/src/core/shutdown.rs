@@ -0,0 +1,52 @@
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::info;
+
+/// Set once SIGTERM or SIGINT is received. Readiness reports not ready from
+/// then on, and the server stops accepting connections and drains.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    pub fn request(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Resolves once shutdown has been requested.
+    pub async fn requested(&self) {
+        let mut receiver = self.sender.subscribe();
+        // The sender lives as long as `self`, so this cannot fail.
+        let _ = receiver.wait_for(|requested| *requested).await;
+    }
+
+    pub fn listen_for_signals(&self) -> std::io::Result<()> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let shutdown = self.clone();
+
+        tokio::spawn(async move {
+            let name = tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = interrupt.recv() => "SIGINT",
+            };
+            info!("FAUCET: Received {}, shutting down", name);
+            shutdown.request();
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,497 @@
+use super::shutdown::Shutdown;
+use crate::config::{AppConfig, TlsClientAuth};
+use anyhow::{anyhow, Context, Result};
+use arc_swap::ArcSwap;
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto, graceful::GracefulShutdown},
+};
+use rustls::{
+    crypto::ring::default_provider,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::{debug, error, info, warn};
+
+/// Connections that have not finished the handshake by then are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client certificate presented on the connection a request arrived on.
+/// Only set when it was verified against `TLS_CLIENT_CA_FILE`.
+#[derive(Clone, Debug)]
+pub struct ClientCertificate {
+    /// SHA-256 of the leaf certificate, as lowercase hex.
+    pub fingerprint: String,
+}
+
+/// The certificate, key and client CA files the TLS listener is built from.
+#[derive(Clone)]
+struct TlsFiles {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    client_auth: TlsClientAuth,
+}
+
+impl TlsFiles {
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        [Some(&self.cert), Some(&self.key), self.client_ca.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    fn load(&self) -> Result<ServerConfig> {
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("Failed to read certificates from {}", self.cert.display()))?;
+        if certs.is_empty() {
+            return Err(anyhow!("No certificates found in {}", self.cert.display()));
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key)
+            .with_context(|| format!("Failed to read private key from {}", self.key.display()))?;
+
+        let provider = Arc::new(default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(path)
+                    .with_context(|| format!("Failed to read client CA {}", path.display()))?
+                {
+                    let cert = cert
+                        .with_context(|| format!("Failed to read client CA {}", path.display()))?;
+                    roots
+                        .add(cert)
+                        .with_context(|| format!("Invalid client CA in {}", path.display()))?;
+                }
+
+                let mut verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+                if self.client_auth == TlsClientAuth::Optional {
+                    verifier = verifier.allow_unauthenticated();
+                }
+                builder.with_client_cert_verifier(verifier.build()?)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .context("Certificate and private key do not match")?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+/// Serves HTTPS from `TLS_CERT_FILE` and `TLS_KEY_FILE`, optionally
+/// verifying client certificates against `TLS_CLIENT_CA_FILE`. The files are
+/// reloaded on SIGHUP or when they change; connections that are already open
+/// keep the certificate they started with.
+pub struct TlsServer {
+    files: TlsFiles,
+    config: Arc<ArcSwap<ServerConfig>>,
+}
+
+impl TlsServer {
+    /// Returns `None` when TLS is not configured.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>> {
+        let (Some(cert), Some(key)) = (&config.tls_cert_file, &config.tls_key_file) else {
+            return Ok(None);
+        };
+
+        let files = TlsFiles {
+            cert: PathBuf::from(cert),
+            key: PathBuf::from(key),
+            client_ca: config.tls_client_ca_file.as_ref().map(PathBuf::from),
+            client_auth: config.tls_client_auth,
+        };
+        let server_config = files.load()?;
+
+        Ok(Some(Self {
+            files,
+            config: Arc::new(ArcSwap::from_pointee(server_config)),
+        }))
+    }
+
+    /// Watches for SIGHUP, and polls the files' modification times every
+    /// `poll_interval` (zero disables polling).
+    pub fn spawn_reloader(&self, poll_interval: Duration) -> Result<JoinHandle<()>> {
+        let mut hangup = signal(SignalKind::hangup())?;
+        let files = self.files.clone();
+        let config = self.config.clone();
+
+        Ok(tokio::spawn(async move {
+            let polling = !poll_interval.is_zero();
+            let mut ticker = tokio::time::interval(poll_interval.max(Duration::from_secs(1)));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut last_modified = files.modified();
+
+            loop {
+                tokio::select! {
+                    _ = hangup.recv() => {
+                        last_modified = files.modified();
+                    }
+                    _ = ticker.tick(), if polling => {
+                        let modified = files.modified();
+                        if modified == last_modified {
+                            continue;
+                        }
+                        last_modified = modified;
+                    }
+                }
+
+                let loading = files.clone();
+                let loaded = tokio::task::spawn_blocking(move || loading.load())
+                    .await
+                    .unwrap_or_else(|e| Err(e.into()));
+                match loaded {
+                    Ok(next) => {
+                        config.store(Arc::new(next));
+                        info!("FAUCET: TLS certificate reloaded");
+                    }
+                    Err(e) => error!(
+                        "FAUCET: TLS certificate reload failed, keeping the current one: {:#}",
+                        e
+                    ),
+                }
+            }
+        }))
+    }
+
+    /// Accepts connections until shutdown is requested, then waits for the
+    /// open ones to finish their requests.
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        router: Router,
+        shutdown: Shutdown,
+    ) -> std::io::Result<()> {
+        let builder = auto::Builder::new(TokioExecutor::new());
+        let graceful = GracefulShutdown::new();
+
+        loop {
+            let (stream, remote) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        // Usually running out of file descriptors; back off
+                        // instead of spinning.
+                        warn!("FAUCET: Failed to accept connection: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+                _ = shutdown.requested() => break,
+            };
+
+            let acceptor = TlsAcceptor::from(self.config.load_full());
+            let builder = builder.clone();
+            let router = router.clone();
+            let watcher = graceful.watcher();
+
+            tokio::spawn(async move {
+                let stream =
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(e)) => {
+                            debug!("FAUCET: TLS handshake with {} failed: {}", remote, e);
+                            return;
+                        }
+                        Err(_) => {
+                            debug!("FAUCET: TLS handshake with {} timed out", remote);
+                            return;
+                        }
+                    };
+
+                let client_certificate = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                    .map(|leaf| ClientCertificate {
+                        fingerprint: hex::encode(Sha256::digest(leaf)),
+                    });
+
+                let service = service_fn(move |mut request: Request<Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(remote));
+                    if let Some(certificate) = &client_certificate {
+                        request.extensions_mut().insert(certificate.clone());
+                    }
+                    // `Router` is always ready, so it can be called without `poll_ready`.
+                    router.clone().call(request)
+                });
+
+                let connection = builder
+                    .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                    .into_owned();
+                if let Err(e) = watcher.watch(connection).await {
+                    debug!(
+                        "FAUCET: Connection from {} closed with error: {}",
+                        remote, e
+                    );
+                }
+            });
+        }
+
+        drop(listener);
+        graceful.shutdown().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Application;
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    };
+    use rustls::{pki_types::ServerName, ClientConfig};
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+
+    /// A throwaway CA plus the directory its certificates are written to.
+    struct Pki {
+        dir: tempfile::TempDir,
+        ca: Certificate,
+        ca_key: KeyPair,
+    }
+
+    struct Issued {
+        cert: Certificate,
+        key: KeyPair,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            let ca_key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = params.self_signed(&ca_key).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("ca.pem"), ca.pem()).unwrap();
+            Self { dir, ca, ca_key }
+        }
+
+        fn issue(&self, purpose: ExtendedKeyUsagePurpose) -> Issued {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params.extended_key_usages = vec![purpose];
+            let cert = params.signed_by(&key, &self.ca, &self.ca_key).unwrap();
+            Issued { cert, key }
+        }
+
+        /// Writes `server` as the certificate and key the listener loads.
+        fn install(&self, server: &Issued) {
+            std::fs::write(self.path("server.pem"), server.cert.pem()).unwrap();
+            std::fs::write(self.path("server.key"), server.key.serialize_pem()).unwrap();
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.dir.path().join(name).display().to_string()
+        }
+    }
+
+    fn fingerprint(issued: &Issued) -> String {
+        hex::encode(Sha256::digest(issued.cert.der()))
+    }
+
+    /// Serves the full router over TLS on a local port, reloading the
+    /// certificate files every second.
+    async fn start(pki: &Pki, vars: &[(&str, &str)]) -> SocketAddr {
+        let cert = pki.path("server.pem");
+        let key = pki.path("server.key");
+        let mut all = vec![
+            ("TLS_CERT_FILE", cert.as_str()),
+            ("TLS_KEY_FILE", key.as_str()),
+        ];
+        all.extend_from_slice(vars);
+        let config = AppConfig::for_tests(&all);
+
+        let app = Application::build(config.clone()).await.unwrap();
+        let tls = TlsServer::from_config(&config).unwrap().unwrap();
+        tls.spawn_reloader(Duration::from_secs(1)).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(tls.serve(listener, app.router().clone(), Shutdown::new()));
+        addr
+    }
+
+    struct Response {
+        status: u16,
+        server_certificate: CertificateDer<'static>,
+    }
+
+    /// Sends `GET path` over a fresh TLS connection that trusts the test CA,
+    /// presenting `client` as the client certificate when given.
+    async fn get(
+        addr: SocketAddr,
+        pki: &Pki,
+        client: Option<&Issued>,
+        path: &str,
+    ) -> std::io::Result<Response> {
+        let mut roots = RootCertStore::empty();
+        roots.add(pki.ca.der().clone()).unwrap();
+        let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = match client {
+            Some(client) => builder
+                .with_client_auth_cert(
+                    vec![client.cert.der().clone()],
+                    PrivateKeyDer::try_from(client.key.serialize_der()).unwrap(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        let server_certificate = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // With TLS 1.3 the server checks the client certificate after the
+        // client considers the handshake done, so a rejection only shows up
+        // here. Stop at the end of the headers; the body is not needed.
+        let mut received = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !received.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        let head = String::from_utf8_lossy(&received);
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .expect("response has a status line");
+        Ok(Response {
+            status,
+            server_certificate,
+        })
+    }
+
+    #[tokio::test]
+    async fn serves_requests_over_tls() {
+        let pki = Pki::new();
+        let server = pki.issue(ExtendedKeyUsagePurpose::ServerAuth);
+        pki.install(&server);
+        let addr = start(&pki, &[]).await;
+
+        let response = get(addr, &pki, None, "/healthz").await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.server_certificate.as_ref(),
+            server.cert.der().as_ref()
+        );
+    }
+
+    #[tokio::test]
+    async fn required_client_auth_rejects_clients_without_a_certificate() {
+        let pki = Pki::new();
+        pki.install(&pki.issue(ExtendedKeyUsagePurpose::ServerAuth));
+        let client = pki.issue(ExtendedKeyUsagePurpose::ClientAuth);
+        let ca = pki.path("ca.pem");
+        let addr = start(
+            &pki,
+            &[
+                ("TLS_CLIENT_CA_FILE", ca.as_str()),
+                ("TLS_CLIENT_AUTH", "required"),
+            ],
+        )
+        .await;
+
+        assert!(get(addr, &pki, None, "/healthz").await.is_err());
+        let response = get(addr, &pki, Some(&client), "/healthz").await.unwrap();
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn listed_client_certificate_grants_admin_access() {
+        let pki = Pki::new();
+        pki.install(&pki.issue(ExtendedKeyUsagePurpose::ServerAuth));
+        let admin = pki.issue(ExtendedKeyUsagePurpose::ClientAuth);
+        let other = pki.issue(ExtendedKeyUsagePurpose::ClientAuth);
+        let ca = pki.path("ca.pem");
+        let fingerprints = format!(r#"["{}"]"#, fingerprint(&admin));
+        let addr = start(
+            &pki,
+            &[
+                ("TLS_CLIENT_CA_FILE", ca.as_str()),
+                ("ADMIN_CLIENT_CERT_FINGERPRINTS", fingerprints.as_str()),
+            ],
+        )
+        .await;
+
+        let response = get(addr, &pki, Some(&admin), "/admin/wallets")
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        let response = get(addr, &pki, Some(&other), "/admin/wallets")
+            .await
+            .unwrap();
+        assert_eq!(response.status, 401);
+        let response = get(addr, &pki, None, "/admin/wallets").await.unwrap();
+        assert_eq!(response.status, 401);
+    }
+
+    #[tokio::test]
+    async fn replaced_certificate_is_served_after_reload() {
+        let pki = Pki::new();
+        let first = pki.issue(ExtendedKeyUsagePurpose::ServerAuth);
+        pki.install(&first);
+        let addr = start(&pki, &[]).await;
+
+        let response = get(addr, &pki, None, "/healthz").await.unwrap();
+        assert_eq!(
+            response.server_certificate.as_ref(),
+            first.cert.der().as_ref()
+        );
+
+        let second = pki.issue(ExtendedKeyUsagePurpose::ServerAuth);
+        pki.install(&second);
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            let response = get(addr, &pki, None, "/healthz").await.unwrap();
+            if response.server_certificate.as_ref() == second.cert.der().as_ref() {
+                break;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "replaced certificate was not picked up"
+            );
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
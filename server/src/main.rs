@@ -1,23 +1,25 @@
 mod api;
 mod config;
 mod core;
+mod secret;
 mod services;
+mod telemetry;
 mod utils;
 
 use anyhow::{Context, Result};
 use tracing::{debug, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 pub use config::AppConfig;
 pub use core::{AppState, Application};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    setup_logging();
+    // Logging is configured from the config, so it starts after loading it.
+    let config =
+        AppConfig::load().map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+    let telemetry = telemetry::init(&config)?;
 
     info!("Starting ASI Faucet service");
-
-    let config = AppConfig::from_env();
     info!("Configuration loaded");
 
     debug!("Server config = {:?}", config);
@@ -28,18 +30,11 @@ async fn main() -> Result<()> {
 
     info!("Application built successfully");
 
-    app.run().await.context("Application runtime error")?;
-
-    info!("Application shutting down gracefully");
-    Ok(())
-}
-
-fn setup_logging() {
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| "asi_faucet=info,tower_http=debug,axum=debug".into());
+    let result = app.run().await.context("Application runtime error");
+    if result.is_ok() {
+        info!("Application shutting down gracefully");
+    }
 
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(tracing_subscriber::fmt::layer().with_target(true))
-        .init();
+    telemetry.shutdown();
+    result
 }
@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use zeroize::Zeroizing;
+
+/// A string that never shows up in logs: `Debug` and `Display` print
+/// `[REDACTED]`, and the memory is wiped when the last copy is dropped.
+/// Use `expose_secret` at the point where the value is actually needed.
+///
+/// Copies made from the exposed value are not covered. The faucet wipes the
+/// copy it passes to `node_cli` for each deploy, but copies `node_cli` makes
+/// internally (e.g. the decoded signing key) are outside its control.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(Zeroizing::new(value))
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reads a secret from a file such as a Docker or Kubernetes secret
+    /// mount. Surrounding whitespace, including the trailing newline most
+    /// editors add, is removed.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = Zeroizing::new(
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?,
+        );
+        Ok(Self::new(contents.trim().to_string()))
+    }
+
+    /// Decrypts an Ethereum-style (Web3 Secret Storage) keystore file and
+    /// returns the private key as a hex string.
+    pub fn from_keystore(path: &str, password: &SecretString) -> Result<Self, Box<dyn Error>> {
+        let key = Zeroizing::new(
+            eth_keystore::decrypt_key(path, password.expose_secret())
+                .map_err(|e| format!("Failed to decrypt keystore {}: {}", path, e))?,
+        );
+        Ok(Self::new(hex::encode(key.as_slice())))
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
@@ -0,0 +1,202 @@
+use crate::config::AppConfig;
+use crate::secret::SecretString;
+use crate::services::settings::FaucetSettings;
+use crate::services::wallets::{WalletPool, WalletStatus};
+use anyhow::Result;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// A hanging observer must not block startup or stall the monitor loop.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BalanceLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl BalanceLevel {
+    fn event(self) -> &'static str {
+        match self {
+            Self::Ok => "balance_recovered",
+            Self::Warning => "balance_warning",
+            Self::Critical => "balance_critical",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BalanceAlert {
+    event: &'static str,
+    /// Human-readable summary, shown as the message by Slack-compatible
+    /// incoming webhooks.
+    text: String,
+    balance: String,
+    warning_threshold: u64,
+    critical_threshold: u64,
+    transfers_paused: bool,
+    wallets: Vec<WalletStatus>,
+    timestamp: String,
+}
+
+/// Polls the faucet wallets' balances and compares their total with
+/// `BALANCE_WARNING_THRESHOLD` and `BALANCE_CRITICAL_THRESHOLD`. Crossing a
+/// threshold in either direction logs and posts an alert to
+/// `BALANCE_ALERT_WEBHOOK_URL`; below the critical threshold transfers are
+/// paused until the wallets are topped up.
+#[derive(Clone)]
+pub struct BalanceMonitor {
+    wallets: WalletPool,
+    settings: FaucetSettings,
+    client: Client,
+    webhook_url: Option<SecretString>,
+    warning_threshold: u64,
+    critical_threshold: u64,
+    interval: Duration,
+    level: Arc<Mutex<BalanceLevel>>,
+}
+
+impl BalanceMonitor {
+    pub fn new(config: &AppConfig, wallets: WalletPool, settings: FaucetSettings) -> Result<Self> {
+        let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+
+        Ok(Self {
+            wallets,
+            settings,
+            client,
+            webhook_url: config.balance_alert_webhook_url.clone(),
+            warning_threshold: config.balance_warning_threshold,
+            critical_threshold: config.balance_critical_threshold,
+            interval: Duration::from_secs(config.wallet_balance_check_interval_sec.max(1)),
+            level: Arc::new(Mutex::new(BalanceLevel::Ok)),
+        })
+    }
+
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let monitor = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(monitor.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+                monitor.check().await;
+            }
+        })
+    }
+
+    /// Refreshes the wallet balances and raises or clears alerts. A check
+    /// that times out is retried on the next tick.
+    pub async fn check(&self) {
+        if tokio::time::timeout(CHECK_TIMEOUT, self.wallets.refresh_balances())
+            .await
+            .is_err()
+        {
+            warn!(
+                "FAUCET: Wallet balance check timed out after {}s, retrying in {}s",
+                CHECK_TIMEOUT.as_secs(),
+                self.interval.as_secs()
+            );
+            return;
+        }
+
+        // Without any known balance (e.g. the observer is down) the current
+        // level is kept.
+        let Some(balance) = self.wallets.total_balance() else {
+            return;
+        };
+
+        let level = self.level_of(balance);
+        let previous = std::mem::replace(
+            &mut *self.level.lock().unwrap_or_else(|e| e.into_inner()),
+            level,
+        );
+        if level == previous {
+            return;
+        }
+
+        let paused = level == BalanceLevel::Critical;
+        if paused != self.settings.load().low_balance {
+            self.settings
+                .update(|settings| settings.low_balance = paused);
+        }
+
+        let text = match level {
+            BalanceLevel::Critical => format!(
+                "FAUCET: Wallet balance {} is below the critical threshold {}, transfers are paused",
+                balance, self.critical_threshold
+            ),
+            BalanceLevel::Warning => format!(
+                "FAUCET: Wallet balance {} is below the warning threshold {}",
+                balance, self.warning_threshold
+            ),
+            BalanceLevel::Ok => format!("FAUCET: Wallet balance recovered to {}", balance),
+        };
+        match level {
+            BalanceLevel::Critical => error!("{}", text),
+            BalanceLevel::Warning => warn!("{}", text),
+            BalanceLevel::Ok => info!("{}", text),
+        }
+
+        self.send_alert(BalanceAlert {
+            event: level.event(),
+            text,
+            balance: balance.to_string(),
+            warning_threshold: self.warning_threshold,
+            critical_threshold: self.critical_threshold,
+            transfers_paused: paused,
+            wallets: self.wallets.status(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+        .await;
+    }
+
+    /// A threshold of 0 is disabled.
+    fn level_of(&self, balance: u128) -> BalanceLevel {
+        if balance < self.critical_threshold as u128 {
+            BalanceLevel::Critical
+        } else if balance < self.warning_threshold as u128 {
+            BalanceLevel::Warning
+        } else {
+            BalanceLevel::Ok
+        }
+    }
+
+    async fn send_alert(&self, alert: BalanceAlert) {
+        let Some(url) = &self.webhook_url else {
+            return;
+        };
+
+        let body = match serde_json::to_vec(&alert) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("FAUCET: Failed to encode {} alert: {}", alert.event, e);
+                return;
+            }
+        };
+
+        let result = self
+            .client
+            .post(url.expose_secret())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        if let Err(e) = result {
+            // The URL usually embeds the webhook's credentials.
+            error!(
+                "FAUCET: Failed to send {} alert: {}",
+                alert.event,
+                e.without_url()
+            );
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use crate::config::{AppConfig, CaptchaProvider};
+use crate::secret::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Provider tokens are a few hundred bytes to ~2 KiB; anything longer is
+/// rejected before it is stored or sent to the provider.
+const MAX_TOKEN_LEN: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub enum CaptchaError {
+    /// CAPTCHA is enabled but the request carried no token.
+    Missing,
+    /// The token was already used for an earlier transfer.
+    Replayed,
+    /// The provider rejected the token; holds its error codes.
+    Rejected(String),
+    /// The provider could not be reached or answered unexpectedly.
+    Unavailable(String),
+}
+
+impl fmt::Display for CaptchaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "captcha_token is required"),
+            Self::Replayed => write!(f, "CAPTCHA token was already used"),
+            Self::Rejected(codes) => write!(f, "CAPTCHA verification failed: {}", codes),
+            Self::Unavailable(msg) => write!(f, "CAPTCHA verification unavailable: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CaptchaError {}
+
+/// Checks a CAPTCHA token with the provider that issued it.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    async fn verify(&self, token: &str) -> Result<(), CaptchaError>;
+}
+
+pub fn new_verifier(config: &AppConfig) -> Result<Option<Arc<dyn CaptchaVerifier>>> {
+    let provider = config.captcha_provider;
+
+    let verifier: Arc<dyn CaptchaVerifier> = match provider {
+        CaptchaProvider::None => return Ok(None),
+        CaptchaProvider::Fake => Arc::new(FakeVerifier {
+            expected: config.captcha_secret.clone(),
+        }),
+        CaptchaProvider::Hcaptcha | CaptchaProvider::Turnstile | CaptchaProvider::Recaptcha => {
+            let verify_url = config
+                .captcha_verify_url
+                .clone()
+                .or_else(|| provider.default_verify_url().map(str::to_owned))
+                .context("No CAPTCHA verify URL")?;
+            let secret = config
+                .captcha_secret
+                .clone()
+                .context("CAPTCHA_SECRET is not set")?;
+
+            Arc::new(SiteVerifyVerifier::new(verify_url, secret)?)
+        }
+    };
+
+    info!("FAUCET: CAPTCHA verification enabled ({:?})", provider);
+    Ok(Some(verifier))
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+/// hCaptcha, Cloudflare Turnstile and reCAPTCHA share the same `siteverify`
+/// protocol: the secret and token are POSTed as a form and the answer is a
+/// JSON object with `success` and `error-codes`. Only the URL differs.
+pub struct SiteVerifyVerifier {
+    client: Client,
+    verify_url: String,
+    secret: SecretString,
+}
+
+impl SiteVerifyVerifier {
+    pub fn new(verify_url: String, secret: SecretString) -> Result<Self> {
+        let client = Client::builder().timeout(VERIFY_TIMEOUT).build()?;
+
+        Ok(Self {
+            client,
+            verify_url,
+            secret,
+        })
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for SiteVerifyVerifier {
+    async fn verify(&self, token: &str) -> Result<(), CaptchaError> {
+        let body = self
+            .client
+            .post(&self.verify_url)
+            .form(&[("secret", self.secret.expose_secret()), ("response", token)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| CaptchaError::Unavailable(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| CaptchaError::Unavailable(e.to_string()))?;
+
+        let result: SiteVerifyResponse = serde_json::from_slice(&body)
+            .map_err(|e| CaptchaError::Unavailable(format!("unexpected response: {}", e)))?;
+
+        if result.success {
+            Ok(())
+        } else {
+            Err(CaptchaError::Rejected(result.error_codes.join(", ")))
+        }
+    }
+}
+
+/// Accepts any non-empty token, or only `CAPTCHA_SECRET` when it is set.
+pub struct FakeVerifier {
+    expected: Option<SecretString>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for FakeVerifier {
+    async fn verify(&self, token: &str) -> Result<(), CaptchaError> {
+        match &self.expected {
+            Some(expected) if expected.expose_secret() != token => {
+                Err(CaptchaError::Rejected("invalid-input-response".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs the configured verifier and remembers spent tokens for
+/// `CAPTCHA_TOKEN_TTL_SEC`, so one solved CAPTCHA pays out only once.
+#[derive(Clone)]
+pub struct CaptchaGuard {
+    verifier: Option<Arc<dyn CaptchaVerifier>>,
+    spent: Arc<Mutex<HashMap<String, Instant>>>,
+    ttl: Duration,
+}
+
+impl CaptchaGuard {
+    pub fn new(verifier: Option<Arc<dyn CaptchaVerifier>>, ttl: Duration) -> Self {
+        Self {
+            verifier,
+            spent: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    pub async fn check(&self, token: Option<&str>) -> Result<(), CaptchaError> {
+        let Some(verifier) = &self.verifier else {
+            return Ok(());
+        };
+
+        let token = token
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or(CaptchaError::Missing)?;
+
+        if token.len() > MAX_TOKEN_LEN {
+            return Err(CaptchaError::Rejected("token-too-long".to_string()));
+        }
+
+        // Claim the token before verifying so concurrent requests carrying
+        // the same token cannot both pass. Only accepted tokens stay spent;
+        // keeping rejected ones would let junk submissions fill the map.
+        self.claim(token)?;
+
+        let result = verifier.verify(token).await;
+        match &result {
+            Ok(()) => {}
+            Err(CaptchaError::Unavailable(msg)) => {
+                warn!("FAUCET: CAPTCHA verification unavailable: {}", msg);
+                self.unclaim(token);
+            }
+            Err(_) => self.unclaim(token),
+        }
+        result
+    }
+
+    fn claim(&self, token: &str) -> Result<(), CaptchaError> {
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        let ttl = self.ttl;
+        spent.retain(|_, spent_at| spent_at.elapsed() < ttl);
+
+        if spent.contains_key(token) {
+            return Err(CaptchaError::Replayed);
+        }
+        spent.insert(token.to_owned(), Instant::now());
+        Ok(())
+    }
+
+    fn unclaim(&self, token: &str) {
+        self.spent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for a provider that cannot be reached.
+    struct OutageVerifier;
+
+    #[async_trait]
+    impl CaptchaVerifier for OutageVerifier {
+        async fn verify(&self, _token: &str) -> Result<(), CaptchaError> {
+            Err(CaptchaError::Unavailable("connection refused".to_string()))
+        }
+    }
+
+    fn guard(verifier: impl CaptchaVerifier + 'static) -> CaptchaGuard {
+        CaptchaGuard::new(Some(Arc::new(verifier)), Duration::from_secs(600))
+    }
+
+    fn fake(expected: Option<&str>) -> CaptchaGuard {
+        guard(FakeVerifier {
+            expected: expected.map(|token| SecretString::new(token.to_string())),
+        })
+    }
+
+    fn spent(guard: &CaptchaGuard) -> usize {
+        guard.spent.lock().unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn disabled_guard_accepts_requests_without_token() {
+        let guard = CaptchaGuard::new(None, Duration::from_secs(600));
+
+        assert!(guard.check(None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn requires_a_token() {
+        let guard = fake(None);
+
+        assert!(matches!(
+            guard.check(None).await,
+            Err(CaptchaError::Missing)
+        ));
+        assert!(matches!(
+            guard.check(Some("  ")).await,
+            Err(CaptchaError::Missing)
+        ));
+    }
+
+    #[tokio::test]
+    async fn accepts_a_valid_token() {
+        let guard = fake(Some("solved"));
+
+        assert!(guard.check(Some("solved")).await.is_ok());
+        assert_eq!(spent(&guard), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_an_invalid_token_without_remembering_it() {
+        let guard = fake(Some("solved"));
+
+        assert!(matches!(
+            guard.check(Some("wrong")).await,
+            Err(CaptchaError::Rejected(_))
+        ));
+        assert_eq!(spent(&guard), 0);
+        // Still rejected by the provider, not reported as a replay.
+        assert!(matches!(
+            guard.check(Some("wrong")).await,
+            Err(CaptchaError::Rejected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_replayed_token() {
+        let guard = fake(None);
+
+        assert!(guard.check(Some("solved")).await.is_ok());
+        assert!(matches!(
+            guard.check(Some("solved")).await,
+            Err(CaptchaError::Replayed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_tokens_before_claiming() {
+        let guard = fake(None);
+        let token = "x".repeat(MAX_TOKEN_LEN + 1);
+
+        assert!(matches!(
+            guard.check(Some(&token)).await,
+            Err(CaptchaError::Rejected(_))
+        ));
+        assert_eq!(spent(&guard), 0);
+    }
+
+    #[tokio::test]
+    async fn provider_outage_leaves_the_token_usable() {
+        let guard = guard(OutageVerifier);
+
+        assert!(matches!(
+            guard.check(Some("solved")).await,
+            Err(CaptchaError::Unavailable(_))
+        ));
+        assert_eq!(spent(&guard), 0);
+        assert!(matches!(
+            guard.check(Some("solved")).await,
+            Err(CaptchaError::Unavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn spent_tokens_expire_after_the_ttl() {
+        let guard = CaptchaGuard::new(
+            Some(Arc::new(FakeVerifier { expected: None })),
+            Duration::ZERO,
+        );
+
+        assert!(guard.check(Some("solved")).await.is_ok());
+        assert!(guard.check(Some("solved")).await.is_ok());
+    }
+}
@@ -0,0 +1,328 @@
+use crate::utils::NodeSocket;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_started_at: Option<Instant>,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            trial_started_at: None,
+        }
+    }
+}
+
+/// One circuit breaker per validator. A node whose deploys keep failing is
+/// taken out of rotation even if it still answers `/status`; after the open
+/// period a single half-open trial decides whether it comes back.
+#[derive(Clone)]
+pub struct CircuitBreakers {
+    breakers: Arc<Mutex<HashMap<String, Breaker>>>,
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl CircuitBreakers {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            breakers: Arc::new(Mutex::new(HashMap::new())),
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+        }
+    }
+
+    /// Current state, with an expired open breaker reported as half-open.
+    pub fn state(&self, node: &NodeSocket) -> BreakerState {
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        match breakers.get(&node_key(node)) {
+            Some(breaker) => self.effective_state(breaker),
+            None => BreakerState::Closed,
+        }
+    }
+
+    /// Whether a call could be admitted right now, without reserving it.
+    pub fn is_available(&self, node: &NodeSocket) -> bool {
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        match breakers.get(&node_key(node)) {
+            Some(breaker) => match self.effective_state(breaker) {
+                BreakerState::Closed => true,
+                BreakerState::HalfOpen => !self.trial_in_flight(breaker),
+                BreakerState::Open => false,
+            },
+            None => true,
+        }
+    }
+
+    /// Admits a call to the node. In half-open state only one trial call is
+    /// admitted at a time.
+    pub fn try_acquire(&self, node: &NodeSocket) -> Option<BreakerPermit> {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(node_key(node)).or_default();
+
+        let trial_started_at = match self.effective_state(breaker) {
+            BreakerState::Closed => None,
+            BreakerState::Open => return None,
+            BreakerState::HalfOpen => {
+                if self.trial_in_flight(breaker) {
+                    return None;
+                }
+                if breaker.state == BreakerState::Open {
+                    info!(
+                        "FAUCET: Circuit breaker for node {} is half-open, sending trial transfer",
+                        node_key(node)
+                    );
+                    breaker.state = BreakerState::HalfOpen;
+                }
+                let started = Instant::now();
+                breaker.trial_started_at = Some(started);
+                Some(started)
+            }
+        };
+
+        Some(BreakerPermit {
+            breakers: self.clone(),
+            node: node.clone(),
+            trial_started_at,
+            settled: false,
+        })
+    }
+
+    fn record_success(&self, node: &NodeSocket) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(node_key(node)).or_default();
+
+        if breaker.state != BreakerState::Closed {
+            info!("FAUCET: Circuit breaker for node {} closed", node_key(node));
+        }
+        *breaker = Breaker::default();
+    }
+
+    fn record_failure(&self, node: &NodeSocket) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(node_key(node)).or_default();
+
+        breaker.consecutive_failures += 1;
+        breaker.trial_started_at = None;
+
+        let trips = match breaker.state {
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => breaker.consecutive_failures >= self.failure_threshold,
+            BreakerState::Open => false,
+        };
+
+        if trips {
+            warn!(
+                "FAUCET: Circuit breaker for node {} opened after {} consecutive failures",
+                node_key(node),
+                breaker.consecutive_failures
+            );
+            breaker.state = BreakerState::Open;
+            breaker.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Frees the half-open trial slot without changing the state, so the
+    /// next call becomes the trial.
+    fn release_trial(&self, node: &NodeSocket, trial_started_at: Instant) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(breaker) = breakers.get_mut(&node_key(node)) {
+            if breaker.trial_started_at == Some(trial_started_at) {
+                breaker.trial_started_at = None;
+            }
+        }
+    }
+
+    /// A trial whose permit is leaked stops blocking new trials after one
+    /// open period.
+    fn trial_in_flight(&self, breaker: &Breaker) -> bool {
+        breaker
+            .trial_started_at
+            .is_some_and(|started| started.elapsed() < self.open_duration)
+    }
+
+    fn effective_state(&self, breaker: &Breaker) -> BreakerState {
+        match (breaker.state, breaker.opened_at) {
+            (BreakerState::Open, Some(opened_at)) if opened_at.elapsed() >= self.open_duration => {
+                BreakerState::HalfOpen
+            }
+            (state, _) => state,
+        }
+    }
+}
+
+/// A call admitted by a breaker. Reporting it with `succeeded` or `failed`
+/// moves the breaker; dropping it records a neutral outcome, for calls that
+/// were cancelled or failed for reasons that say nothing about the node.
+#[must_use]
+pub struct BreakerPermit {
+    breakers: CircuitBreakers,
+    node: NodeSocket,
+    trial_started_at: Option<Instant>,
+    settled: bool,
+}
+
+impl BreakerPermit {
+    pub fn node(&self) -> &NodeSocket {
+        &self.node
+    }
+
+    pub fn succeeded(mut self) {
+        self.settled = true;
+        self.breakers.record_success(&self.node);
+    }
+
+    pub fn failed(mut self) {
+        self.settled = true;
+        self.breakers.record_failure(&self.node);
+    }
+}
+
+impl Drop for BreakerPermit {
+    fn drop(&mut self) {
+        if let (false, Some(started)) = (self.settled, self.trial_started_at) {
+            self.breakers.release_trial(&self.node, started);
+        }
+    }
+}
+
+fn node_key(node: &NodeSocket) -> String {
+    format!("{}:{}", node.host, node.grpc_port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN: Duration = Duration::from_millis(50);
+
+    fn node() -> NodeSocket {
+        NodeSocket {
+            host: "127.0.0.1".to_string(),
+            grpc_port: 40401,
+            http_port: 40403,
+            weight: None,
+        }
+    }
+
+    fn fail(breakers: &CircuitBreakers, times: u32) {
+        for _ in 0..times {
+            breakers.try_acquire(&node()).unwrap().failed();
+        }
+    }
+
+    /// Opens the breaker and waits out the open period.
+    fn half_open(breakers: &CircuitBreakers) {
+        fail(breakers, 2);
+        std::thread::sleep(OPEN);
+        assert_eq!(breakers.state(&node()), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn opens_at_the_failure_threshold() {
+        let breakers = CircuitBreakers::new(3, OPEN);
+
+        fail(&breakers, 2);
+        assert_eq!(breakers.state(&node()), BreakerState::Closed);
+        fail(&breakers, 1);
+        assert_eq!(breakers.state(&node()), BreakerState::Open);
+        assert!(!breakers.is_available(&node()));
+        assert!(breakers.try_acquire(&node()).is_none());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+
+        fail(&breakers, 1);
+        breakers.try_acquire(&node()).unwrap().succeeded();
+        fail(&breakers, 1);
+        assert_eq!(breakers.state(&node()), BreakerState::Closed);
+    }
+
+    #[test]
+    fn becomes_half_open_after_the_open_period() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+
+        fail(&breakers, 2);
+        assert_eq!(breakers.state(&node()), BreakerState::Open);
+        std::thread::sleep(OPEN);
+        assert_eq!(breakers.state(&node()), BreakerState::HalfOpen);
+        assert!(breakers.is_available(&node()));
+    }
+
+    #[test]
+    fn admits_one_trial_at_a_time() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+        half_open(&breakers);
+
+        let trial = breakers.try_acquire(&node()).unwrap();
+        assert!(breakers.try_acquire(&node()).is_none());
+        assert!(!breakers.is_available(&node()));
+        drop(trial);
+    }
+
+    #[test]
+    fn successful_trial_closes() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+        half_open(&breakers);
+
+        breakers.try_acquire(&node()).unwrap().succeeded();
+        assert_eq!(breakers.state(&node()), BreakerState::Closed);
+    }
+
+    #[test]
+    fn failed_trial_reopens() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+        half_open(&breakers);
+
+        breakers.try_acquire(&node()).unwrap().failed();
+        assert_eq!(breakers.state(&node()), BreakerState::Open);
+        assert!(breakers.try_acquire(&node()).is_none());
+    }
+
+    #[test]
+    fn neutral_trial_frees_the_slot_without_a_verdict() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+        half_open(&breakers);
+
+        drop(breakers.try_acquire(&node()).unwrap());
+        assert_eq!(breakers.state(&node()), BreakerState::HalfOpen);
+        let next = breakers
+            .try_acquire(&node())
+            .expect("next call is the trial");
+        next.succeeded();
+        assert_eq!(breakers.state(&node()), BreakerState::Closed);
+    }
+
+    #[test]
+    fn neutral_call_from_before_the_trip_keeps_the_trial() {
+        let breakers = CircuitBreakers::new(2, OPEN);
+        let earlier = breakers.try_acquire(&node()).unwrap();
+        half_open(&breakers);
+
+        let trial = breakers.try_acquire(&node()).unwrap();
+        drop(earlier);
+        assert!(breakers.try_acquire(&node()).is_none());
+        drop(trial);
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::{Arc, Mutex};
+
+/// How long a claimed key may stay without a response before another request
+/// with the key may take it over. Only a crash leaves a claim unsettled that
+/// long, since transfers finish well within the request timeout.
+const IN_PROGRESS_LEASE_SEC: i64 = 5 * 60;
+
+pub enum IdempotencyOutcome {
+    /// First time the key is seen, or its earlier claim was abandoned; the
+    /// caller must `complete` or `release` it.
+    New,
+    /// The key was already used with the same request; holds the stored response.
+    Replay(String),
+    /// The original request with this key has not finished yet.
+    InProgress,
+    /// The key was already used with a different request body.
+    Mismatch,
+}
+
+/// Remembers `Idempotency-Key`s of `/transfer` requests together with the
+/// response they produced, so client retries replay the original deploy
+/// instead of creating a new one.
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    conn: Arc<Mutex<Connection>>,
+    ttl_sec: i64,
+}
+
+impl IdempotencyStore {
+    pub fn open(path: &str, ttl_sec: u64) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open idempotency database at {}", path))?;
+
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS idempotency_keys (
+                key          TEXT    PRIMARY KEY,
+                fingerprint  TEXT    NOT NULL,
+                response     TEXT,
+                created_at   INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at
+                ON idempotency_keys (created_at);",
+        )
+        .context("Failed to initialize idempotency schema")?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            ttl_sec: i64::try_from(ttl_sec).unwrap_or(i64::MAX),
+        })
+    }
+
+    /// Claims the key for a request with the given fingerprint, or reports
+    /// what the earlier request with the same key did.
+    pub async fn begin(&self, key: &str, fingerprint: &str) -> Result<IdempotencyOutcome> {
+        let key = key.to_owned();
+        let fingerprint = fingerprint.to_owned();
+        let now = Utc::now().timestamp();
+        let expired_before = now.saturating_sub(self.ttl_sec);
+        let abandoned_before = now.saturating_sub(IN_PROGRESS_LEASE_SEC);
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+
+            tx.execute(
+                "DELETE FROM idempotency_keys WHERE created_at < ?1",
+                params![expired_before],
+            )?;
+
+            let existing: Option<(String, Option<String>, i64)> = tx
+                .query_row(
+                    "SELECT fingerprint, response, created_at FROM idempotency_keys
+                     WHERE key = ?1",
+                    params![key],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            let outcome = match existing {
+                Some((_, None, claimed_at)) if claimed_at < abandoned_before => {
+                    tx.execute(
+                        "UPDATE idempotency_keys SET fingerprint = ?2, created_at = ?3
+                         WHERE key = ?1",
+                        params![key, fingerprint, now],
+                    )?;
+                    IdempotencyOutcome::New
+                }
+                Some((stored, _, _)) if stored != fingerprint => IdempotencyOutcome::Mismatch,
+                Some((_, Some(response), _)) => IdempotencyOutcome::Replay(response),
+                Some((_, None, _)) => IdempotencyOutcome::InProgress,
+                None => {
+                    tx.execute(
+                        "INSERT INTO idempotency_keys (key, fingerprint, response, created_at)
+                         VALUES (?1, ?2, NULL, ?3)",
+                        params![key, fingerprint, now],
+                    )?;
+                    IdempotencyOutcome::New
+                }
+            };
+
+            tx.commit()?;
+            Ok(outcome)
+        })
+        .await
+    }
+
+    /// Stores the response to replay for later requests with this key.
+    pub async fn complete(&self, key: &str, response: &str) -> Result<()> {
+        let key = key.to_owned();
+        let response = response.to_owned();
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "UPDATE idempotency_keys SET response = ?2 WHERE key = ?1",
+                params![key, response],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Forgets a key whose request failed before anything was submitted, so
+    /// the client may retry with it.
+    pub async fn release(&self, key: &str) -> Result<()> {
+        let key = key.to_owned();
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM idempotency_keys WHERE key = ?1 AND response IS NULL",
+                params![key],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Idempotency connection lock poisoned"))?;
+            f(&mut conn).context("Idempotency query failed")
+        })
+        .await
+        .context("Idempotency task panicked")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> IdempotencyStore {
+        IdempotencyStore::open(":memory:", 86400).unwrap()
+    }
+
+    async fn outcome(store: &IdempotencyStore, fingerprint: &str) -> &'static str {
+        match store.begin("key-1", fingerprint).await.unwrap() {
+            IdempotencyOutcome::New => "new",
+            IdempotencyOutcome::Replay(_) => "replay",
+            IdempotencyOutcome::InProgress => "in_progress",
+            IdempotencyOutcome::Mismatch => "mismatch",
+        }
+    }
+
+    fn age_claim(store: &IdempotencyStore, seconds: i64) {
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE idempotency_keys SET created_at = created_at - ?1",
+                params![seconds],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn second_request_waits_for_the_first() {
+        let store = store();
+
+        assert_eq!(outcome(&store, "a").await, "new");
+        assert_eq!(outcome(&store, "a").await, "in_progress");
+    }
+
+    #[tokio::test]
+    async fn completed_key_replays_the_response() {
+        let store = store();
+
+        outcome(&store, "a").await;
+        store.complete("key-1", "{}").await.unwrap();
+
+        match store.begin("key-1", "a").await.unwrap() {
+            IdempotencyOutcome::Replay(response) => assert_eq!(response, "{}"),
+            _ => panic!("expected a replay"),
+        }
+    }
+
+    #[tokio::test]
+    async fn released_key_can_be_claimed_again() {
+        let store = store();
+
+        outcome(&store, "a").await;
+        store.release("key-1").await.unwrap();
+
+        assert_eq!(outcome(&store, "a").await, "new");
+    }
+
+    #[tokio::test]
+    async fn different_body_is_a_mismatch() {
+        let store = store();
+
+        outcome(&store, "a").await;
+        assert_eq!(outcome(&store, "b").await, "mismatch");
+    }
+
+    #[tokio::test]
+    async fn abandoned_claim_is_taken_over_after_the_lease() {
+        let store = store();
+
+        outcome(&store, "a").await;
+        age_claim(&store, IN_PROGRESS_LEASE_SEC - 10);
+        assert_eq!(outcome(&store, "a").await, "in_progress");
+
+        age_claim(&store, 20);
+        assert_eq!(outcome(&store, "a").await, "new");
+        assert_eq!(outcome(&store, "a").await, "in_progress");
+    }
+
+    #[tokio::test]
+    async fn stored_response_outlives_the_lease() {
+        let store = store();
+
+        outcome(&store, "a").await;
+        store.complete("key-1", "{}").await.unwrap();
+        age_claim(&store, IN_PROGRESS_LEASE_SEC + 10);
+
+        assert_eq!(outcome(&store, "a").await, "replay");
+    }
+}
@@ -0,0 +1,208 @@
+use crate::services::ledger::CooldownReservation;
+use crate::services::transfer::TransferService;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tracing::{error, info, info_span, Instrument, Span};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Submitting,
+    Submitted { deploy_id: String },
+    Failed { error: String },
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, Self::Submitted { .. } | Self::Failed { .. })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransferJob {
+    pub id: String,
+    pub to_address: String,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct QueueFull;
+
+type QueuedJob = (String, Span, CooldownReservation);
+
+/// How often `drain` checks whether the unfinished jobs are done.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Queue of asynchronous transfers processed by a fixed pool of workers.
+/// Job state is kept in memory; finished jobs are dropped after the
+/// retention period.
+#[derive(Clone)]
+pub struct TransferJobs {
+    jobs: Arc<RwLock<HashMap<String, TransferJob>>>,
+    /// Job IDs with a span parented to the request that queued them, and the
+    /// cooldown reservation the job holds until it is settled.
+    sender: mpsc::Sender<QueuedJob>,
+    retention: Duration,
+}
+
+impl TransferJobs {
+    pub fn start(
+        transfers: TransferService,
+        workers: usize,
+        queue_size: usize,
+        retention: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(queue_size.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let jobs = Self {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            sender,
+            retention,
+        };
+
+        for _ in 0..workers.max(1) {
+            let worker = jobs.clone();
+            let receiver = receiver.clone();
+            let transfers = transfers.clone();
+            tokio::spawn(async move { worker.run_worker(receiver, transfers).await });
+        }
+
+        jobs
+    }
+
+    /// Queues a transfer to the reserved address. The reservation keeps any
+    /// other request for the address out while the job is queued.
+    pub fn enqueue(&self, reservation: CooldownReservation) -> Result<String, QueueFull> {
+        self.prune();
+
+        let to_address = reservation.address().to_owned();
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let job = TransferJob {
+            id: id.clone(),
+            to_address: to_address.clone(),
+            status: JobStatus::Queued,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.jobs
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.clone(), job);
+
+        let span = info_span!("transfer.job", job_id = %id, recipient = %to_address);
+        // On failure the reservation is dropped, giving the address back.
+        if self
+            .sender
+            .try_send((id.clone(), span, reservation))
+            .is_err()
+        {
+            self.jobs
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&id);
+            return Err(QueueFull);
+        }
+
+        info!("FAUCET: Queued transfer job {} for {}", id, to_address);
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<TransferJob> {
+        self.jobs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+    }
+
+    /// Waits until every job has finished or `deadline` has passed, and
+    /// returns the jobs that never reached the submission queue. Jobs being
+    /// submitted by then are pending in the `TransferService`.
+    pub async fn drain(&self, deadline: Instant) -> Vec<TransferJob> {
+        loop {
+            let unfinished: Vec<TransferJob> = self
+                .jobs
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .values()
+                .filter(|job| !job.status.is_finished())
+                .cloned()
+                .collect();
+
+            let now = Instant::now();
+            if unfinished.is_empty() || now >= deadline {
+                return unfinished
+                    .into_iter()
+                    .filter(|job| matches!(job.status, JobStatus::Queued))
+                    .collect();
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    fn set_status(&self, id: &str, status: JobStatus) {
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(job) = jobs.get_mut(id) {
+            job.status = status;
+            job.updated_at = Utc::now();
+        }
+    }
+
+    fn prune(&self) {
+        let Ok(retention) = chrono::Duration::from_std(self.retention) else {
+            return;
+        };
+        let cutoff = Utc::now() - retention;
+
+        self.jobs
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, job| !job.status.is_finished() || job.updated_at > cutoff);
+    }
+
+    async fn run_worker(
+        self,
+        receiver: Arc<Mutex<mpsc::Receiver<QueuedJob>>>,
+        transfers: TransferService,
+    ) {
+        loop {
+            let next = receiver.lock().await.recv().await;
+            let Some((id, span, reservation)) = next else {
+                break;
+            };
+
+            let Some(job) = self.get(&id) else {
+                continue;
+            };
+
+            self.set_status(&id, JobStatus::Submitting);
+
+            let status = match transfers.execute(reservation).instrument(span).await {
+                Ok(deploy_id) => JobStatus::Submitted { deploy_id },
+                Err(e) => {
+                    error!(
+                        "FAUCET: Transfer job {} to {} failed: {}",
+                        id, job.to_address, e
+                    );
+                    JobStatus::Failed {
+                        error: e.to_string(),
+                    }
+                }
+            };
+
+            self.set_status(&id, status);
+        }
+    }
+}
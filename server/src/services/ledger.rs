@@ -0,0 +1,427 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tracing::error;
+
+pub enum ReserveOutcome {
+    /// The address may claim; the transfer must be settled through the
+    /// reservation.
+    Reserved(CooldownReservation),
+    /// The address is cooling down, or a transfer to it is under way, for
+    /// this long.
+    CoolingDown(Duration),
+}
+
+/// Persistent record of successful faucet transfers, used to enforce the
+/// per-address cooldown across restarts.
+#[derive(Clone)]
+pub struct TransferLedger {
+    conn: Arc<Mutex<Connection>>,
+    cooldown: Duration,
+}
+
+impl TransferLedger {
+    pub fn open(path: &str, cooldown_sec: u64) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open ledger database at {}", path))?;
+
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .context("Failed to enable WAL journal mode")?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transfers (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                address     TEXT    NOT NULL,
+                amount      INTEGER NOT NULL,
+                deploy_id   TEXT    NOT NULL,
+                created_at  INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_transfers_address_created_at
+                ON transfers (address, created_at);
+            CREATE TABLE IF NOT EXISTS cooldown_resets (
+                address     TEXT    PRIMARY KEY,
+                reset_at    INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS cooldown_reservations (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                address     TEXT    NOT NULL,
+                reserved_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_cooldown_reservations_address
+                ON cooldown_reservations (address, reserved_at);",
+        )
+        .context("Failed to initialize ledger schema")?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            cooldown: Duration::from_secs(cooldown_sec),
+        })
+    }
+
+    /// Starts the address's cooldown unless it is already running. Checking
+    /// and reserving happen in one transaction, so of several concurrent
+    /// requests for an address only one gets the reservation.
+    pub async fn reserve(&self, address: &str) -> Result<ReserveOutcome> {
+        let owned = address.to_owned();
+        let cooldown = self.cooldown;
+        let now = Utc::now().timestamp();
+        let expired_before = now.saturating_sub(cooldown.as_secs() as i64);
+
+        let reserved = self
+            .with_conn(move |conn| {
+                let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+                tx.execute(
+                    "DELETE FROM cooldown_reservations WHERE reserved_at < ?1",
+                    params![expired_before],
+                )?;
+
+                let outcome = match cooldown_remaining_at(&tx, &owned, cooldown, now)? {
+                    Some(remaining) => Err(remaining),
+                    None => {
+                        tx.execute(
+                            "INSERT INTO cooldown_reservations (address, reserved_at)
+                             VALUES (?1, ?2)",
+                            params![owned, now],
+                        )?;
+                        Ok(tx.last_insert_rowid())
+                    }
+                };
+
+                tx.commit()?;
+                Ok(outcome)
+            })
+            .await?;
+
+        Ok(match reserved {
+            Ok(id) => ReserveOutcome::Reserved(CooldownReservation {
+                id,
+                address: address.to_owned(),
+                ledger: self.clone(),
+                release_on_drop: true,
+            }),
+            Err(remaining) => ReserveOutcome::CoolingDown(remaining),
+        })
+    }
+
+    /// Lifts the cooldown of the address without touching its transfer
+    /// history. Returns whether a cooldown was active.
+    /// Reservations are dropped with it; a transfer still under way is
+    /// recorded when it finishes and starts a new cooldown.
+    pub async fn clear_cooldown(&self, address: &str) -> Result<bool> {
+        let address = address.to_owned();
+        let cooldown = self.cooldown;
+        let now = Utc::now().timestamp();
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let was_active = cooldown_remaining_at(&tx, &address, cooldown, now)?.is_some();
+
+            tx.execute(
+                "INSERT INTO cooldown_resets (address, reset_at) VALUES (?1, ?2)
+                 ON CONFLICT (address) DO UPDATE SET reset_at = excluded.reset_at",
+                params![address, now],
+            )?;
+            tx.execute(
+                "DELETE FROM cooldown_reservations WHERE address = ?1",
+                params![address],
+            )?;
+
+            tx.commit()?;
+            Ok(was_active)
+        })
+        .await
+    }
+
+    /// Copies the write-ahead log into the database file and truncates it,
+    /// so the file alone holds every recorded transfer. The idempotency
+    /// store shares the file and is flushed with it.
+    pub async fn checkpoint(&self) -> Result<()> {
+        self.with_conn(|conn| conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())))
+            .await
+    }
+
+    /// Drops the reservations held for the address, for transfers that were
+    /// abandoned before they were submitted.
+    pub async fn release_reservations(&self, address: &str) -> Result<()> {
+        let address = address.to_owned();
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM cooldown_reservations WHERE address = ?1",
+                params![address],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Replaces the reservation with the transfer it was taken for.
+    async fn promote(
+        &self,
+        reservation_id: i64,
+        address: &str,
+        amount: u64,
+        deploy_id: &str,
+    ) -> Result<()> {
+        let address = address.to_owned();
+        let deploy_id = deploy_id.to_owned();
+        let amount = i64::try_from(amount).context("Transfer amount does not fit into ledger")?;
+        let created_at = Utc::now().timestamp();
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM cooldown_reservations WHERE id = ?1",
+                params![reservation_id],
+            )?;
+            tx.execute(
+                "INSERT INTO transfers (address, amount, deploy_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![address, amount, deploy_id, created_at],
+            )?;
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn release(&self, reservation_id: i64) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM cooldown_reservations WHERE id = ?1",
+                params![reservation_id],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Ledger connection lock poisoned"))?;
+            f(&mut conn).context("Ledger query failed")
+        })
+        .await
+        .context("Ledger task panicked")?
+    }
+}
+
+/// Cooldown left for the address at `now` (a Unix timestamp), counting its
+/// last transfer since the latest reset and any reservation held for it.
+fn cooldown_remaining_at(
+    conn: &Connection,
+    address: &str,
+    cooldown: Duration,
+    now: i64,
+) -> rusqlite::Result<Option<Duration>> {
+    let reset_at: Option<i64> = conn
+        .query_row(
+            "SELECT reset_at FROM cooldown_resets WHERE address = ?1",
+            params![address],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let transferred_at: Option<i64> = conn.query_row(
+        "SELECT MAX(created_at) FROM transfers WHERE address = ?1",
+        params![address],
+        |row| row.get(0),
+    )?;
+    let transferred_at = transferred_at
+        .filter(|created_at| !matches!(reset_at, Some(reset) if *created_at <= reset));
+    let reserved_at: Option<i64> = conn.query_row(
+        "SELECT MAX(reserved_at) FROM cooldown_reservations WHERE address = ?1",
+        params![address],
+        |row| row.get(0),
+    )?;
+
+    let Some(since) = transferred_at.max(reserved_at) else {
+        return Ok(None);
+    };
+    let elapsed = Duration::from_secs(now.saturating_sub(since).max(0) as u64);
+
+    Ok(cooldown.checked_sub(elapsed).filter(|d| !d.is_zero()))
+}
+
+/// Holds an address's cooldown while a transfer to it is under way, so
+/// concurrent requests for the same address are turned away.
+///
+/// Dropped before `mark_deploying`, it gives the address back. After that
+/// the deploy may have reached a node, so the reservation is kept until
+/// `record_transfer` or `release` settles it, or the cooldown runs out.
+pub struct CooldownReservation {
+    id: i64,
+    address: String,
+    ledger: TransferLedger,
+    release_on_drop: bool,
+}
+
+impl CooldownReservation {
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn mark_deploying(&mut self) {
+        self.release_on_drop = false;
+    }
+
+    /// Records the transfer, which takes over the cooldown.
+    pub async fn record_transfer(mut self, amount: u64, deploy_id: &str) -> Result<()> {
+        self.release_on_drop = false;
+        self.ledger
+            .promote(self.id, &self.address, amount, deploy_id)
+            .await
+    }
+
+    /// Gives the address back after a transfer that certainly did not reach
+    /// a node.
+    pub async fn release(mut self) -> Result<()> {
+        self.release_on_drop = false;
+        self.ledger.release(self.id).await
+    }
+}
+
+impl Drop for CooldownReservation {
+    fn drop(&mut self) {
+        if !self.release_on_drop {
+            return;
+        }
+        // Outside a runtime (at shutdown) the reservation simply expires
+        // with the cooldown.
+        let Ok(handle) = Handle::try_current() else {
+            return;
+        };
+
+        let ledger = self.ledger.clone();
+        let id = self.id;
+        let address = std::mem::take(&mut self.address);
+        handle.spawn(async move {
+            if let Err(e) = ledger.release(id).await {
+                error!(
+                    "FAUCET: Failed to release cooldown reservation of {}: {}",
+                    address, e
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> TransferLedger {
+        TransferLedger::open(":memory:", 3600).unwrap()
+    }
+
+    async fn reserve(ledger: &TransferLedger, address: &str) -> Option<CooldownReservation> {
+        match ledger.reserve(address).await.unwrap() {
+            ReserveOutcome::Reserved(reservation) => Some(reservation),
+            ReserveOutcome::CoolingDown(_) => None,
+        }
+    }
+
+    /// Lets the release spawned by a dropped reservation run.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn only_one_concurrent_reservation_succeeds() {
+        let ledger = ledger();
+
+        let attempts = (0..16).map(|_| {
+            let ledger = ledger.clone();
+            tokio::spawn(async move { ledger.reserve("1111a").await.unwrap() })
+        });
+        let mut reserved = Vec::new();
+        for attempt in attempts {
+            if let ReserveOutcome::Reserved(reservation) = attempt.await.unwrap() {
+                reserved.push(reservation);
+            }
+        }
+
+        assert_eq!(reserved.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reservation_is_per_address() {
+        let ledger = ledger();
+
+        let _first = reserve(&ledger, "1111a").await.unwrap();
+        assert!(reserve(&ledger, "1111b").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn recorded_transfer_keeps_the_cooldown() {
+        let ledger = ledger();
+
+        let reservation = reserve(&ledger, "1111a").await.unwrap();
+        reservation.record_transfer(10, "deploy-1").await.unwrap();
+
+        assert!(reserve(&ledger, "1111a").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn released_reservation_frees_the_address() {
+        let ledger = ledger();
+
+        let reservation = reserve(&ledger, "1111a").await.unwrap();
+        reservation.release().await.unwrap();
+
+        assert!(reserve(&ledger, "1111a").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn dropping_before_deploy_releases() {
+        let ledger = ledger();
+
+        drop(reserve(&ledger, "1111a").await.unwrap());
+        settle().await;
+
+        assert!(reserve(&ledger, "1111a").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn dropping_after_deploy_started_keeps_the_cooldown() {
+        let ledger = ledger();
+
+        let mut reservation = reserve(&ledger, "1111a").await.unwrap();
+        reservation.mark_deploying();
+        drop(reservation);
+        settle().await;
+
+        assert!(reserve(&ledger, "1111a").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn clearing_the_cooldown_drops_reservations() {
+        let ledger = ledger();
+
+        let mut reservation = reserve(&ledger, "1111a").await.unwrap();
+        reservation.mark_deploying();
+        assert!(ledger.clear_cooldown("1111a").await.unwrap());
+
+        assert!(reserve(&ledger, "1111a").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn releasing_an_abandoned_address_frees_it() {
+        let ledger = ledger();
+
+        let reservation = reserve(&ledger, "1111a").await.unwrap();
+        ledger.release_reservations("1111a").await.unwrap();
+        assert!(reserve(&ledger, "1111a").await.is_some());
+        drop(reservation);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::services::circuit_breaker::{BreakerState, CircuitBreakers};
+use crate::services::node_cli::NodeCliError;
+use crate::services::node_health::NodeHealth;
+use crate::services::wallets::WalletStatus;
+use crate::utils::NodeSocket;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Duration;
+
+/// Node calls include deploys that may wait on the network for a while.
+const NODE_CALL_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Why a transfer request was turned down before a deploy was attempted.
+#[derive(Debug, Clone, Copy)]
+pub enum Rejection {
+    Paused,
+    LowBalance,
+    InvalidAddress,
+    ProofOfWork,
+    Captcha,
+    Cooldown,
+    OverBalanceLimit,
+    RateLimited,
+    QueueFull,
+}
+
+impl Rejection {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Paused => "paused",
+            Self::LowBalance => "low_balance",
+            Self::InvalidAddress => "invalid_address",
+            Self::ProofOfWork => "proof_of_work",
+            Self::Captcha => "captcha",
+            Self::Cooldown => "cooldown",
+            Self::OverBalanceLimit => "over_balance_limit",
+            Self::RateLimited => "rate_limited",
+            Self::QueueFull => "queue_full",
+        }
+    }
+}
+
+/// Prometheus metrics. Counters and histograms are updated as events
+/// happen; node and wallet gauges are filled in from their monitors when
+/// `/metrics` is scraped.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    node_calls: HistogramVec,
+    node_errors: IntCounterVec,
+    node_up: IntGaugeVec,
+    node_latency: GaugeVec,
+    node_circuit_open: IntGaugeVec,
+    wallet_balance: GaugeVec,
+    wallet_eligible: IntGaugeVec,
+    transfers: IntCounter,
+    distributed: IntCounter,
+    rejections: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("faucet".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )?;
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let node_calls = HistogramVec::new(
+            HistogramOpts::new("node_call_duration_seconds", "Latency of node CLI calls")
+                .buckets(NODE_CALL_BUCKETS.to_vec()),
+            &["operation", "node"],
+        )?;
+        let node_errors = IntCounterVec::new(
+            Opts::new("node_call_errors_total", "Failed node CLI calls by kind"),
+            &["operation", "node", "kind"],
+        )?;
+        let node_up = IntGaugeVec::new(
+            Opts::new("node_up", "Whether the node passed its last health probe"),
+            &["node"],
+        )?;
+        let node_latency = GaugeVec::new(
+            Opts::new(
+                "node_probe_latency_seconds",
+                "Latency of the node's last health probe",
+            ),
+            &["node"],
+        )?;
+        let node_circuit_open = IntGaugeVec::new(
+            Opts::new(
+                "node_circuit_open",
+                "Whether the node's circuit breaker is open",
+            ),
+            &["node"],
+        )?;
+        let wallet_balance = GaugeVec::new(
+            Opts::new(
+                "wallet_balance",
+                "Last known faucet wallet balance in smallest token unit",
+            ),
+            &["address"],
+        )?;
+        let wallet_eligible = IntGaugeVec::new(
+            Opts::new(
+                "wallet_eligible",
+                "Whether the faucet wallet is used for new transfers",
+            ),
+            &["address"],
+        )?;
+        let transfers = IntCounter::new("transfers_total", "Transfers deployed")?;
+        let distributed = IntCounter::new(
+            "distributed_amount_total",
+            "Amount sent by deployed transfers in smallest token unit",
+        )?;
+        let rejections = IntCounterVec::new(
+            Opts::new(
+                "transfer_rejections_total",
+                "Transfer requests rejected before a deploy, by reason",
+            ),
+            &["reason"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_duration.clone()))?;
+        registry.register(Box::new(node_calls.clone()))?;
+        registry.register(Box::new(node_errors.clone()))?;
+        registry.register(Box::new(node_up.clone()))?;
+        registry.register(Box::new(node_latency.clone()))?;
+        registry.register(Box::new(node_circuit_open.clone()))?;
+        registry.register(Box::new(wallet_balance.clone()))?;
+        registry.register(Box::new(wallet_eligible.clone()))?;
+        registry.register(Box::new(transfers.clone()))?;
+        registry.register(Box::new(distributed.clone()))?;
+        registry.register(Box::new(rejections.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_duration,
+            node_calls,
+            node_errors,
+            node_up,
+            node_latency,
+            node_circuit_open,
+            wallet_balance,
+            wallet_eligible,
+            transfers,
+            distributed,
+            rejections,
+        })
+    }
+
+    /// `route` is the matched route template (e.g. `/balance/:address`), so
+    /// addresses and IDs do not become labels.
+    pub fn observe_http(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_node_call<T>(
+        &self,
+        operation: &str,
+        node: &str,
+        elapsed: Duration,
+        result: &Result<T, NodeCliError>,
+    ) {
+        self.node_calls
+            .with_label_values(&[operation, node])
+            .observe(elapsed.as_secs_f64());
+
+        if let Err(e) = result {
+            self.node_errors
+                .with_label_values(&[operation, node, e.kind()])
+                .inc();
+        }
+    }
+
+    pub fn record_transfer(&self, amount: u64) {
+        self.transfers.inc();
+        self.distributed.inc_by(amount);
+    }
+
+    pub fn record_rejection(&self, reason: Rejection) {
+        self.rejections.with_label_values(&[reason.label()]).inc();
+    }
+
+    /// Renders all metrics in the Prometheus text format, after refreshing
+    /// the gauges from the current node and wallet state.
+    pub fn render(
+        &self,
+        nodes: &[NodeHealth],
+        breakers: &CircuitBreakers,
+        wallets: &[WalletStatus],
+    ) -> prometheus::Result<String> {
+        // Reset first so removed nodes and wallets disappear.
+        self.node_up.reset();
+        self.node_latency.reset();
+        self.node_circuit_open.reset();
+        for health in nodes {
+            let node = node_label(&health.node);
+            self.node_up
+                .with_label_values(&[&node])
+                .set(health.up as i64);
+            if let Some(latency) = health.last_latency {
+                self.node_latency
+                    .with_label_values(&[&node])
+                    .set(latency.as_secs_f64());
+            }
+            let open = breakers.state(&health.node) == BreakerState::Open;
+            self.node_circuit_open
+                .with_label_values(&[&node])
+                .set(open as i64);
+        }
+
+        self.wallet_balance.reset();
+        self.wallet_eligible.reset();
+        for wallet in wallets {
+            if let Some(balance) = wallet
+                .balance
+                .as_deref()
+                .and_then(|b| b.parse::<f64>().ok())
+            {
+                self.wallet_balance
+                    .with_label_values(&[&wallet.address])
+                    .set(balance);
+            }
+            self.wallet_eligible
+                .with_label_values(&[&wallet.address])
+                .set(wallet.eligible as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+pub fn node_label(node: &NodeSocket) -> String {
+    format!("{}:{}", node.host, node.grpc_port)
+}
@@ -1 +1,14 @@
+pub mod balance_monitor;
+pub mod captcha;
+pub mod circuit_breaker;
+pub mod idempotency;
+pub mod jobs;
+pub mod ledger;
+pub mod metrics;
 pub mod node_cli;
+pub mod node_health;
+pub mod node_selector;
+pub mod pow;
+pub mod settings;
+pub mod transfer;
+pub mod wallets;
@@ -1,32 +1,296 @@
 use crate::config::AppConfig;
-use crate::utils::choose_random_node;
-use crate::utils::NodeSocket;
-use anyhow::Result;
+use crate::secret::SecretString;
+use crate::services::circuit_breaker::{BreakerPermit, CircuitBreakers};
+use crate::services::metrics::{node_label, Metrics};
+use crate::services::node_health::{NodeHealth, NodeHealthMonitor};
+use crate::services::node_selector::{new_selector, NodeSelector};
+use crate::utils::{NodeSocket, REQUEST_TIMEOUT, TOKEN_SYMBOL};
 use node_cli::{
     args::{HttpArgs, TransferArgs, WaitArgs, WalletBalanceArgs},
     commands::{check_deploy_status, transfer_deploy, wallet_balance_command},
     utils::output::DeployCompressedInfo,
 };
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tracing::{instrument, warn, Span};
+use zeroize::Zeroize;
+
+/// Total time a transfer may spend on connection probes and deploys across
+/// all attempts, so it fails on its own before the request times out.
+const TRANSFER_BUDGET: Duration = REQUEST_TIMEOUT.saturating_sub(Duration::from_secs(1));
+
+/// Failure of a `node_cli` call, classified from the error it returned.
+#[derive(Debug, Clone)]
+pub enum NodeCliError {
+    /// Every validator is down or has its circuit breaker open.
+    NoAvailableNode,
+    /// A connection could not be established, so nothing was sent over it.
+    Unreachable(String),
+    /// A connection failed after the deploy was started, so it may already
+    /// have been accepted.
+    Interrupted(String),
+    /// The call did not finish in time. A deploy may still have been accepted.
+    Timeout(String),
+    InsufficientFunds(String),
+    InvalidAddress(String),
+    NotFound(String),
+    Other(String),
+}
+
+impl NodeCliError {
+    fn classify(message: String) -> Self {
+        let lower = message.to_lowercase();
+        let contains_any = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+
+        if contains_any(&["insufficient", "not enough funds", "not enough balance"]) {
+            Self::InsufficientFunds(message)
+        } else if contains_any(&["invalid address", "invalid rev address", "address must"]) {
+            Self::InvalidAddress(message)
+        } else if contains_any(&[
+            "connection refused",
+            "tcp connect error",
+            "error trying to connect",
+            "failed to connect",
+            "dns error",
+            "no route to host",
+        ]) {
+            Self::Unreachable(message)
+        } else if contains_any(&["timed out", "timeout", "deadline exceeded"]) {
+            Self::Timeout(message)
+        } else if contains_any(&["not found"]) {
+            Self::NotFound(message)
+        } else {
+            Self::Other(message)
+        }
+    }
+
+    /// Short, stable name of the variant, used as a metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NoAvailableNode => "no_available_node",
+            Self::Unreachable(_) => "unreachable",
+            Self::Interrupted(_) => "interrupted",
+            Self::Timeout(_) => "timeout",
+            Self::InsufficientFunds(_) => "insufficient_funds",
+            Self::InvalidAddress(_) => "invalid_address",
+            Self::NotFound(_) => "not_found",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Whether the transfer certainly did not reach a node, so the recipient
+    /// may be paid by a later request. Anything unrecognised counts as
+    /// possibly submitted.
+    pub fn proves_not_submitted(&self) -> bool {
+        matches!(
+            self,
+            Self::NoAvailableNode
+                | Self::Unreachable(_)
+                | Self::InvalidAddress(_)
+                | Self::InsufficientFunds(_)
+        )
+    }
+}
+
+impl fmt::Display for NodeCliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAvailableNode => write!(f, "No reachable nodes"),
+            Self::Unreachable(msg) => write!(f, "Node unreachable: {}", msg),
+            Self::Interrupted(msg) => write!(f, "Node connection lost during deploy: {}", msg),
+            Self::Timeout(msg) => write!(f, "Node call timed out: {}", msg),
+            Self::InsufficientFunds(msg) => write!(f, "Insufficient funds: {}", msg),
+            Self::InvalidAddress(msg) => write!(f, "Invalid address: {}", msg),
+            Self::NotFound(msg) => write!(f, "Not found: {}", msg),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for NodeCliError {}
+
+/// A failed transfer attempt, by the phase it failed in.
+#[derive(Debug)]
+enum TransferFailure {
+    /// The validator's gRPC port did not accept a connection before the
+    /// deploy was started. Nothing was sent, so another node may be tried.
+    Connect(NodeCliError),
+    /// `transfer_deploy` itself failed. It talks to both the validator and
+    /// the observer, and the deploy may already have been accepted.
+    Deploy(NodeCliError),
+}
+
+impl TransferFailure {
+    /// A connection failure only proves nothing was sent before the deploy
+    /// started; inside `transfer_deploy` it may be the observer failing after
+    /// the validator accepted the deploy.
+    fn into_error(self) -> NodeCliError {
+        match self {
+            Self::Connect(e) => e,
+            Self::Deploy(NodeCliError::Unreachable(message)) => NodeCliError::Interrupted(message),
+            Self::Deploy(e) => e,
+        }
+    }
+}
+
+/// `TransferArgs` holding a plain copy of a faucet key, which is wiped when
+/// the arguments are dropped, including when the deploy is cancelled.
+struct KeyedTransferArgs(TransferArgs);
+
+impl Deref for KeyedTransferArgs {
+    type Target = TransferArgs;
+
+    fn deref(&self) -> &TransferArgs {
+        &self.0
+    }
+}
+
+impl Drop for KeyedTransferArgs {
+    fn drop(&mut self) {
+        self.0.private_key.zeroize();
+    }
+}
 
 #[derive(Clone)]
 pub struct NodeCliService {
     config: AppConfig,
+    node_health: NodeHealthMonitor,
+    selector: Arc<dyn NodeSelector>,
+    breakers: CircuitBreakers,
+    metrics: Metrics,
 }
 
 impl NodeCliService {
-    pub fn new(config: AppConfig) -> Self {
-        Self { config }
+    pub fn new(config: AppConfig, node_health: NodeHealthMonitor, metrics: Metrics) -> Self {
+        let selector = new_selector(config.node_selection_strategy);
+        let breakers = CircuitBreakers::new(
+            config.circuit_breaker_failure_threshold,
+            Duration::from_secs(config.circuit_breaker_open_sec),
+        );
+
+        Self {
+            config,
+            node_health,
+            selector,
+            breakers,
+            metrics,
+        }
+    }
+
+    pub fn circuit_breakers(&self) -> &CircuitBreakers {
+        &self.breakers
+    }
+
+    /// Selects a healthy node whose circuit breaker admits a call, skipping
+    /// the nodes in `exclude`.
+    fn acquire_node(&self, exclude: &[NodeSocket]) -> Result<BreakerPermit, NodeCliError> {
+        let mut candidates: Vec<NodeHealth> = self
+            .node_health
+            .healthy_nodes()
+            .into_iter()
+            .filter(|health| !exclude.contains(&health.node))
+            .filter(|health| self.breakers.is_available(&health.node))
+            .collect();
+
+        while let Some(selected) = self.selector.select(&candidates) {
+            let node = selected.node.clone();
+            if let Some(permit) = self.breakers.try_acquire(&node) {
+                return Ok(permit);
+            }
+            candidates.retain(|health| health.node != node);
+        }
+
+        Err(NodeCliError::NoAvailableNode)
+    }
+
+    /// Submits the transfer, moving on to another validator only when the
+    /// previous one refused the connection. All attempts together are kept
+    /// within `TRANSFER_BUDGET`.
+    pub async fn transfer_funds(
+        &self,
+        to_address: &str,
+        amount: u64,
+        private_key: &SecretString,
+    ) -> Result<String, NodeCliError> {
+        let max_attempts = self.config.transfer_max_attempts.max(1) as usize;
+        let connect_timeout = Duration::from_secs(self.config.node_health_check_timeout_sec);
+        let deadline = Instant::now() + TRANSFER_BUDGET;
+        let mut tried: Vec<NodeSocket> = Vec::new();
+
+        loop {
+            let permit = self.acquire_node(&tried)?;
+            let node_socket = permit.node().clone();
+
+            match self
+                .submit_transfer(
+                    &node_socket,
+                    permit,
+                    to_address,
+                    amount,
+                    private_key.expose_secret(),
+                    deadline,
+                )
+                .await
+            {
+                Err(TransferFailure::Connect(_))
+                    if tried.len() + 1 < max_attempts
+                        && Instant::now() + connect_timeout < deadline =>
+                {
+                    warn!(
+                        "FAUCET: Node {}:{} unreachable, retrying transfer to {} on another node",
+                        node_socket.host, node_socket.grpc_port, to_address
+                    );
+                    tried.push(node_socket);
+                }
+                result => return result.map_err(TransferFailure::into_error),
+            }
+        }
     }
 
-    pub async fn transfer_funds(&self, to_address: &str, private_key: String) -> Result<String> {
-        let amount = self.config.faucet_amount;
-        let node_socket: &NodeSocket = choose_random_node(&self.config.node_sockets).await?;
+    #[instrument(
+        name = "node.transfer",
+        skip_all,
+        fields(
+            otel.kind = "client",
+            otel.status_code,
+            node.host = %node_socket.host,
+            node.port = node_socket.grpc_port,
+            recipient = to_address,
+            amount = amount,
+            deploy_id,
+            error,
+        )
+    )]
+    async fn submit_transfer(
+        &self,
+        node_socket: &NodeSocket,
+        permit: BreakerPermit,
+        to_address: &str,
+        amount: u64,
+        private_key: &str,
+        deadline: Instant,
+    ) -> Result<String, TransferFailure> {
+        let connect_timeout = Duration::from_secs(self.config.node_health_check_timeout_sec)
+            .min(deadline.saturating_duration_since(Instant::now()));
+        if let Err(e) = probe(node_socket, connect_timeout).await {
+            warn!(
+                "FAUCET: Node {}:{} refused the connection: {}",
+                node_socket.host, node_socket.grpc_port, e
+            );
+            permit.failed();
+            let result = Err(e);
+            record_outcome(&result);
+            return result.map_err(TransferFailure::Connect);
+        }
 
-        let args = &TransferArgs {
-            token: "ASI".to_string(),
+        let args = &KeyedTransferArgs(TransferArgs {
+            token: TOKEN_SYMBOL.to_string(),
             to_address: to_address.to_owned(),
             amount,
-            private_key: private_key,
+            private_key: private_key.to_owned(),
             host: node_socket.host.clone(),
             grpc_port: node_socket.grpc_port,
             http_port: node_socket.http_port,
@@ -36,31 +300,105 @@ impl NodeCliService {
             check_interval: 5, // unused
             observer_host: Some(self.config.observer_host.clone()),
             observer_grpc_port: Some(self.config.observer_grpc_port),
+        });
+
+        let deploy_timeout = Duration::from_secs(self.config.node_deploy_timeout_sec)
+            .min(deadline.saturating_duration_since(Instant::now()));
+
+        let started = Instant::now();
+        let result = match tokio::time::timeout(deploy_timeout, transfer_deploy(args)).await {
+            Ok(Ok(deploy_id)) => Ok(deploy_id.to_string()),
+            Ok(Err(e)) => Err(NodeCliError::classify(e.to_string())),
+            Err(_) => Err(NodeCliError::Timeout(format!(
+                "no response within {}ms",
+                deploy_timeout.as_millis()
+            ))),
         };
+        self.metrics.observe_node_call(
+            "transfer_funds",
+            &node_label(node_socket),
+            started.elapsed(),
+            &result,
+        );
+        record_outcome(&result);
+        if let Ok(deploy_id) = &result {
+            Span::current().record("deploy_id", deploy_id.as_str());
+        }
 
-        let deploy_id = transfer_deploy(args)
-            .await
-            .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+        match &result {
+            Ok(_) => permit.succeeded(),
+            Err(e) => {
+                warn!(
+                    "FAUCET: Deploy to node {}:{} failed: {}",
+                    node_socket.host, node_socket.grpc_port, e
+                );
+                // Rejections caused by the request itself say nothing about
+                // the node's health, and the validator accepted a connection
+                // moments ago, so a connection failure here is most likely
+                // the observer. Dropping the permit records a neutral outcome.
+                if !matches!(
+                    e,
+                    NodeCliError::InvalidAddress(_)
+                        | NodeCliError::InsufficientFunds(_)
+                        | NodeCliError::Unreachable(_)
+                ) {
+                    permit.failed();
+                }
+            }
+        }
 
-        Ok(deploy_id.to_string())
+        result.map_err(TransferFailure::Deploy)
     }
 
-    pub async fn get_balance(&self, address: &str) -> Result<String> {
+    #[instrument(
+        name = "node.get_balance",
+        skip_all,
+        fields(
+            otel.kind = "client",
+            otel.status_code,
+            node.host = %self.config.observer_host,
+            node.port = self.config.observer_grpc_port,
+            address = address,
+            error,
+        )
+    )]
+    pub async fn get_balance(&self, address: &str) -> Result<String, NodeCliError> {
         let args = WalletBalanceArgs {
-            token: "ASI".to_string(),
+            token: TOKEN_SYMBOL.to_string(),
             address: address.to_owned(),
             host: self.config.observer_host.clone(),
             grpc_port: self.config.observer_grpc_port,
         };
 
-        let (balance, _meta) = wallet_balance_command(&args)
+        let started = Instant::now();
+        let result = wallet_balance_command(&args)
             .await
-            .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+            .map(|(balance, _meta)| balance)
+            .map_err(|e| NodeCliError::classify(e.to_string()));
+        self.metrics.observe_node_call(
+            "get_balance",
+            &self.observer_label(),
+            started.elapsed(),
+            &result,
+        );
+        record_outcome(&result);
 
-        Ok(balance)
+        result
     }
 
-    pub async fn get_deploy_info(&self, id: String) -> Result<DeployCompressedInfo> {
+    #[instrument(
+        name = "node.get_deploy_info",
+        skip_all,
+        fields(
+            otel.kind = "client",
+            otel.status_code,
+            node.host = %self.config.observer_host,
+            node.port = self.config.observer_grpc_port,
+            deploy_id = %id,
+            error,
+        )
+    )]
+    pub async fn get_deploy_info(&self, id: String) -> Result<DeployCompressedInfo, NodeCliError> {
         let max_wait = self.config.deploy_max_wait_sec;
         let check_interval = self.config.deploy_check_interval_sec;
         let max_attempts = max_wait / check_interval;
@@ -77,10 +415,271 @@ impl NodeCliService {
             observer_grpc_port: self.config.observer_grpc_port,
         };
 
-        let deploy_info = check_deploy_status(id.clone(), &args)
+        let started = Instant::now();
+        let result = check_deploy_status(id.clone(), &args)
             .await
-            .map_err(|e| anyhow::Error::msg(e.to_string()))?;
+            .map_err(|e| NodeCliError::classify(e.to_string()));
+        self.metrics.observe_node_call(
+            "get_deploy_info",
+            &self.observer_label(),
+            started.elapsed(),
+            &result,
+        );
+        record_outcome(&result);
+
+        result
+    }
+
+    fn observer_label(&self) -> String {
+        format!(
+            "{}:{}",
+            self.config.observer_host, self.config.observer_grpc_port
+        )
+    }
+}
+
+/// Checks that the validator accepts connections on its gRPC port.
+async fn probe(node_socket: &NodeSocket, timeout: Duration) -> Result<(), NodeCliError> {
+    let address = (node_socket.host.as_str(), node_socket.grpc_port);
+    match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(NodeCliError::Unreachable(e.to_string())),
+        Err(_) => Err(NodeCliError::Unreachable(format!(
+            "no connection within {}ms",
+            timeout.as_millis()
+        ))),
+    }
+}
+
+/// Marks the current node call span as failed, with the error kind.
+fn record_outcome<T>(result: &Result<T, NodeCliError>) {
+    if let Err(e) = result {
+        let span = Span::current();
+        span.record("otel.status_code", "ERROR");
+        span.record("error", e.kind());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::{
+        export::trace::SpanData, testing::trace::InMemorySpanExporter, trace::TracerProvider,
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn kind_of(message: &str) -> &'static str {
+        NodeCliError::classify(message.to_string()).kind()
+    }
+
+    #[test]
+    fn classifies_insufficient_funds() {
+        assert_eq!(kind_of("Insufficient funds"), "insufficient_funds");
+        assert_eq!(
+            kind_of("vault has not enough balance"),
+            "insufficient_funds"
+        );
+        assert_eq!(
+            kind_of("Not enough funds for transfer"),
+            "insufficient_funds"
+        );
+    }
+
+    #[test]
+    fn classifies_invalid_address() {
+        assert_eq!(kind_of("Invalid address: 1111xyz"), "invalid_address");
+        assert_eq!(kind_of("invalid REV address"), "invalid_address");
+        assert_eq!(kind_of("address must start with 1111"), "invalid_address");
+    }
+
+    #[test]
+    fn classifies_unreachable() {
+        assert_eq!(
+            kind_of("transport error: tcp connect error: Connection refused (os error 111)"),
+            "unreachable"
+        );
+        assert_eq!(kind_of("error trying to connect: dns error"), "unreachable");
+        assert_eq!(kind_of("Failed to connect to observer"), "unreachable");
+        assert_eq!(kind_of("No route to host"), "unreachable");
+    }
+
+    #[test]
+    fn classifies_timeout() {
+        assert_eq!(kind_of("operation timed out"), "timeout");
+        assert_eq!(
+            kind_of("status: DeadlineExceeded, deadline exceeded"),
+            "timeout"
+        );
+        assert_eq!(kind_of("request timeout"), "timeout");
+    }
+
+    #[test]
+    fn classifies_not_found() {
+        assert_eq!(kind_of("Deploy not found"), "not_found");
+    }
+
+    #[test]
+    fn falls_back_to_other() {
+        assert_eq!(kind_of("status: Internal, message: casper error"), "other");
+        assert_eq!(kind_of(""), "other");
+    }
+
+    #[test]
+    fn request_errors_take_precedence_over_transport_wording() {
+        // A node rejecting the deploy may mention the connection it used.
+        assert_eq!(
+            kind_of("insufficient funds (connection refused by vault)"),
+            "insufficient_funds"
+        );
+        assert_eq!(
+            kind_of("invalid address, timed out parsing"),
+            "invalid_address"
+        );
+    }
+
+    #[test]
+    fn keeps_the_original_message() {
+        match NodeCliError::classify("Connection refused".to_string()) {
+            NodeCliError::Unreachable(message) => assert_eq!(message, "Connection refused"),
+            other => panic!("unexpected classification: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn only_failures_before_submission_prove_nothing_was_sent() {
+        assert!(NodeCliError::NoAvailableNode.proves_not_submitted());
+        assert!(NodeCliError::classify("Connection refused".to_string()).proves_not_submitted());
+        assert!(NodeCliError::classify("Invalid address".to_string()).proves_not_submitted());
+        assert!(NodeCliError::classify("Insufficient funds".to_string()).proves_not_submitted());
+
+        assert!(!NodeCliError::classify("timed out".to_string()).proves_not_submitted());
+        assert!(!NodeCliError::classify("Deploy not found".to_string()).proves_not_submitted());
+        assert!(!NodeCliError::classify("casper error".to_string()).proves_not_submitted());
+    }
+
+    #[test]
+    fn connection_failures_during_the_deploy_do_not_prove_nothing_was_sent() {
+        let refused = || NodeCliError::classify("Connection refused".to_string());
+
+        let before = TransferFailure::Connect(refused()).into_error();
+        assert!(matches!(before, NodeCliError::Unreachable(_)));
+        assert!(before.proves_not_submitted());
+
+        let during = TransferFailure::Deploy(refused()).into_error();
+        assert!(matches!(during, NodeCliError::Interrupted(_)));
+        assert!(!during.proves_not_submitted());
+
+        let rejected = TransferFailure::Deploy(NodeCliError::classify("Invalid address".into()));
+        assert!(rejected.into_error().proves_not_submitted());
+    }
+
+    #[test]
+    fn transfer_budget_ends_before_the_request_timeout() {
+        assert!(TRANSFER_BUDGET < REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn probe_reports_a_closed_port_as_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let node = NodeSocket {
+            host: "127.0.0.1".to_string(),
+            grpc_port: port,
+            http_port: port,
+            weight: None,
+        };
+        let result = probe(&node, Duration::from_secs(1)).await;
+        assert!(matches!(result, Err(NodeCliError::Unreachable(_))));
+    }
+
+    #[tokio::test]
+    async fn probe_accepts_an_open_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let node = NodeSocket {
+            host: "127.0.0.1".to_string(),
+            grpc_port: port,
+            http_port: port,
+            weight: None,
+        };
+        assert!(probe(&node, Duration::from_secs(1)).await.is_ok());
+    }
+
+    fn attribute(span: &SpanData, key: &str) -> Option<String> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.to_string())
+    }
+
+    #[tokio::test]
+    async fn transfer_spans_carry_node_deploy_and_recipient() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        // A node that passes the health check but does not speak gRPC, so a
+        // real deploy fails after the connection phase.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port().to_string();
+        let status = axum::Router::new().route("/status", axum::routing::get(|| async { "ok" }));
+        tokio::spawn(async move { axum::serve(listener, status).await });
+
+        let ports = format!("[{}]", port);
+        let config = AppConfig::for_tests(&[
+            ("NODE_GRPC_PORTS", &ports),
+            ("NODE_HTTP_PORTS", &ports),
+            ("OBSERVER_GRPC_PORT", &port),
+            ("OBSERVER_HTTP_PORT", &port),
+            ("TRANSFER_MAX_ATTEMPTS", "1"),
+            ("DEPLOY_MAX_WAIT_SEC", "1"),
+            ("DEPLOY_CHECK_INTERVAL_SEC", "1"),
+        ]);
+        let node_health = NodeHealthMonitor::new(
+            &config.node_sockets,
+            config.observer_socket(),
+            Duration::from_secs(10),
+            Duration::from_secs(2),
+        )
+        .unwrap();
+        node_health.probe_all().await;
+        let service = NodeCliService::new(config.clone(), node_health, Metrics::new().unwrap());
+
+        let recipient = "1111recipient";
+        let key = config.private_keys[0].clone();
+        let transferred = service.transfer_funds(recipient, 100, &key).await;
+        let _ = service.get_deploy_info("deploy-1".to_string()).await;
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let transfer = spans
+            .iter()
+            .find(|span| span.name == "node.transfer")
+            .expect("node.transfer span");
+        assert_eq!(
+            attribute(transfer, "node.host").as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(attribute(transfer, "node.port"), Some(port.clone()));
+        assert_eq!(attribute(transfer, "recipient").as_deref(), Some(recipient));
+        match &transferred {
+            Ok(deploy_id) => assert_eq!(attribute(transfer, "deploy_id"), Some(deploy_id.clone())),
+            Err(e) => assert_eq!(attribute(transfer, "error").as_deref(), Some(e.kind())),
+        }
 
-        Ok(deploy_info)
+        let deploy = spans
+            .iter()
+            .find(|span| span.name == "node.get_deploy_info")
+            .expect("node.get_deploy_info span");
+        assert_eq!(attribute(deploy, "deploy_id").as_deref(), Some("deploy-1"));
+        assert_eq!(attribute(deploy, "node.host").as_deref(), Some("127.0.0.1"));
+        assert_eq!(attribute(deploy, "node.port"), Some(port));
     }
 }
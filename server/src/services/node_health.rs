@@ -0,0 +1,196 @@
+use crate::utils::NodeSocket;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone)]
+pub struct NodeHealth {
+    pub node: NodeSocket,
+    pub up: bool,
+    pub last_latency: Option<Duration>,
+    pub consecutive_failures: u32,
+    pub last_checked: Option<DateTime<Utc>>,
+}
+
+impl NodeHealth {
+    fn unknown(node: NodeSocket) -> Self {
+        Self {
+            node,
+            up: false,
+            last_latency: None,
+            consecutive_failures: 0,
+            last_checked: None,
+        }
+    }
+}
+
+/// Probes every validator's `/status` endpoint in the background and keeps
+/// a shared health table, so transfers can pick a node without a network
+/// round-trip. The observer node, which serves balance and deploy queries,
+/// is probed alongside.
+#[derive(Clone)]
+pub struct NodeHealthMonitor {
+    table: Arc<RwLock<Vec<NodeHealth>>>,
+    observer: Arc<RwLock<NodeHealth>>,
+    client: Client,
+    interval: Duration,
+}
+
+impl NodeHealthMonitor {
+    pub fn new(
+        nodes: &[NodeSocket],
+        observer: NodeSocket,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let client = Client::builder().timeout(timeout).build()?;
+        let table = nodes.iter().cloned().map(NodeHealth::unknown).collect();
+
+        Ok(Self {
+            table: Arc::new(RwLock::new(table)),
+            observer: Arc::new(RwLock::new(NodeHealth::unknown(observer))),
+            client,
+            interval,
+        })
+    }
+
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let monitor = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(monitor.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+                monitor.probe_all().await;
+            }
+        })
+    }
+
+    pub async fn probe_all(&self) {
+        let nodes: Vec<NodeSocket> = self.snapshot().into_iter().map(|h| h.node).collect();
+
+        let observer = self.observer().node;
+
+        let mut probes = JoinSet::new();
+        for node in nodes {
+            let client = self.client.clone();
+            probes.spawn(async move {
+                let started = Instant::now();
+                let up = probe(&client, &node).await;
+                (node, up, started.elapsed())
+            });
+        }
+
+        let started = Instant::now();
+        let observer_up = probe(&self.client, &observer).await;
+        apply_probe(
+            &mut self.observer.write().unwrap_or_else(|e| e.into_inner()),
+            "Observer",
+            observer_up,
+            started.elapsed(),
+        );
+
+        while let Some(result) = probes.join_next().await {
+            let Ok((node, up, latency)) = result else {
+                continue;
+            };
+            self.record(&node, up, latency);
+        }
+    }
+
+    /// Replaces the node list. Nodes that stay keep their health; new nodes
+    /// start as down until their first probe.
+    pub fn set_nodes(&self, nodes: &[NodeSocket]) {
+        let mut table = self.table.write().unwrap_or_else(|e| e.into_inner());
+
+        *table = nodes
+            .iter()
+            .map(
+                |node| match table.iter().find(|health| health.node.same_endpoint(node)) {
+                    Some(health) => NodeHealth {
+                        node: node.clone(),
+                        ..health.clone()
+                    },
+                    None => NodeHealth::unknown(node.clone()),
+                },
+            )
+            .collect();
+    }
+
+    /// Results are matched by endpoint, since the node list may have been
+    /// replaced while the probe was running.
+    fn record(&self, node: &NodeSocket, up: bool, latency: Duration) {
+        let mut table = self.table.write().unwrap_or_else(|e| e.into_inner());
+        let Some(health) = table
+            .iter_mut()
+            .find(|health| health.node.same_endpoint(node))
+        else {
+            return;
+        };
+
+        apply_probe(health, "Node", up, latency);
+    }
+
+    pub fn snapshot(&self) -> Vec<NodeHealth> {
+        self.table.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn observer(&self) -> NodeHealth {
+        self.observer
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn healthy_nodes(&self) -> Vec<NodeHealth> {
+        self.table
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|h| h.up)
+            .cloned()
+            .collect()
+    }
+}
+
+fn apply_probe(health: &mut NodeHealth, kind: &str, up: bool, latency: Duration) {
+    let was_up = health.up;
+    let first_check = health.last_checked.is_none();
+    health.last_checked = Some(Utc::now());
+
+    if up {
+        health.up = true;
+        health.last_latency = Some(latency);
+        health.consecutive_failures = 0;
+    } else {
+        health.up = false;
+        health.consecutive_failures += 1;
+    }
+
+    let node = &health.node;
+    match (was_up || first_check, up) {
+        (true, false) => warn!(
+            "FAUCET: {} {}:{} is down ({} consecutive failures)",
+            kind, node.host, node.http_port, health.consecutive_failures
+        ),
+        (false, true) => info!("FAUCET: {} {}:{} is up", kind, node.host, node.http_port),
+        _ => debug!(
+            "FAUCET: {} {}:{} up={} latency={:?}",
+            kind, node.host, node.http_port, up, latency
+        ),
+    }
+}
+
+async fn probe(client: &Client, node: &NodeSocket) -> bool {
+    let url = format!("http://{}:{}/status", node.host, node.http_port);
+    match client.get(&url).send().await {
+        Ok(resp) => resp.status().is_success(),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,68 @@
+use crate::config::NodeSelectionStrategy;
+use crate::services::node_health::NodeHealth;
+use rand::{rng, seq::IndexedRandom};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Picks the validator a transfer is sent to. Candidates are the nodes that
+/// are currently up according to the health monitor.
+pub trait NodeSelector: Send + Sync {
+    fn select<'a>(&self, candidates: &'a [NodeHealth]) -> Option<&'a NodeHealth>;
+}
+
+pub fn new_selector(strategy: NodeSelectionStrategy) -> Arc<dyn NodeSelector> {
+    match strategy {
+        NodeSelectionStrategy::Random => Arc::new(RandomSelector),
+        NodeSelectionStrategy::RoundRobin => Arc::new(RoundRobinSelector::default()),
+        NodeSelectionStrategy::LeastLatency => Arc::new(LeastLatencySelector),
+        NodeSelectionStrategy::Weighted => Arc::new(WeightedSelector),
+    }
+}
+
+pub struct RandomSelector;
+
+impl NodeSelector for RandomSelector {
+    fn select<'a>(&self, candidates: &'a [NodeHealth]) -> Option<&'a NodeHealth> {
+        candidates.choose(&mut rng())
+    }
+}
+
+#[derive(Default)]
+pub struct RoundRobinSelector {
+    next: AtomicUsize,
+}
+
+impl NodeSelector for RoundRobinSelector {
+    fn select<'a>(&self, candidates: &'a [NodeHealth]) -> Option<&'a NodeHealth> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        candidates.get(index % candidates.len())
+    }
+}
+
+/// Prefers the node with the lowest latency on its last health probe.
+pub struct LeastLatencySelector;
+
+impl NodeSelector for LeastLatencySelector {
+    fn select<'a>(&self, candidates: &'a [NodeHealth]) -> Option<&'a NodeHealth> {
+        candidates
+            .iter()
+            .min_by_key(|h| h.last_latency.unwrap_or(std::time::Duration::MAX))
+    }
+}
+
+/// Picks nodes at random in proportion to their weight. Nodes with weight 0
+/// only receive traffic when no weighted node is up.
+pub struct WeightedSelector;
+
+impl NodeSelector for WeightedSelector {
+    fn select<'a>(&self, candidates: &'a [NodeHealth]) -> Option<&'a NodeHealth> {
+        candidates
+            .choose_weighted(&mut rng(), |h| h.node.weight())
+            .ok()
+            .or_else(|| candidates.choose(&mut rng()))
+    }
+}
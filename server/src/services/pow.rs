@@ -0,0 +1,332 @@
+use crate::config::AppConfig;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Window over which issued challenges are counted to estimate load.
+const LOAD_WINDOW: Duration = Duration::from_secs(60);
+const MAX_NONCE_LEN: usize = 64;
+/// Hard cap; beyond this a browser cannot solve a challenge in time.
+const DIFFICULTY_LIMIT: u8 = 32;
+
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub challenge: String,
+    pub difficulty: u8,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum PowError {
+    /// Proof of work is enabled but the request carried no solution.
+    Missing,
+    Malformed,
+    BadSignature,
+    Expired,
+    /// The hash does not have enough leading zero bits.
+    Insufficient,
+    /// The challenge was already redeemed.
+    Replayed,
+}
+
+impl fmt::Display for PowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "pow_challenge and pow_nonce are required"),
+            Self::Malformed => write!(f, "Malformed proof-of-work challenge or nonce"),
+            Self::BadSignature => {
+                write!(f, "Proof-of-work challenge was not issued by this faucet")
+            }
+            Self::Expired => write!(f, "Proof-of-work challenge expired"),
+            Self::Insufficient => write!(f, "Proof-of-work solution does not meet the difficulty"),
+            Self::Replayed => write!(f, "Proof-of-work challenge was already used"),
+        }
+    }
+}
+
+impl std::error::Error for PowError {}
+
+/// Hashcash-style challenges for `/transfer`.
+///
+/// A challenge is `salt.difficulty.expires_at.signature`, signed with an
+/// HMAC so the faucet keeps no state until it is redeemed. A solution is a
+/// nonce such that `SHA-256("{challenge}:{to_address}:{nonce}")` starts with
+/// `difficulty` zero bits. Issuing `POW_LOAD_THRESHOLD` challenges a minute
+/// adds one bit of difficulty, and every further doubling another.
+#[derive(Clone)]
+pub struct ProofOfWork {
+    key: Arc<Vec<u8>>,
+    base_difficulty: u8,
+    max_difficulty: u8,
+    load_threshold: u32,
+    ttl: Duration,
+    issued: Arc<Mutex<VecDeque<Instant>>>,
+    spent: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl ProofOfWork {
+    /// Returns `None` when `POW_DIFFICULTY` is 0.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        if config.pow_difficulty == 0 {
+            return None;
+        }
+
+        let key = match &config.pow_secret {
+            Some(secret) => secret.expose_secret().as_bytes().to_vec(),
+            None => {
+                warn!("FAUCET: POW_SECRET not set, challenges will not survive a restart");
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+
+        let max_difficulty = config.pow_max_difficulty.min(DIFFICULTY_LIMIT);
+        info!(
+            "FAUCET: Proof of work enabled (difficulty {}-{})",
+            config.pow_difficulty, max_difficulty
+        );
+
+        Some(Self {
+            key: Arc::new(key),
+            base_difficulty: config.pow_difficulty.min(max_difficulty),
+            max_difficulty,
+            load_threshold: config.pow_load_threshold.max(1),
+            ttl: Duration::from_secs(config.pow_challenge_ttl_sec),
+            issued: Arc::new(Mutex::new(VecDeque::new())),
+            spent: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn issue(&self) -> Challenge {
+        let difficulty = self.record_issue();
+        let salt = hex::encode(rand::random::<[u8; 16]>());
+        let expires_at = Utc::now().timestamp() + self.ttl.as_secs() as i64;
+
+        let payload = format!("{}.{}.{}", salt, difficulty, expires_at);
+        let signature = hex::encode(self.mac(&payload).finalize().into_bytes());
+
+        Challenge {
+            challenge: format!("{}.{}", payload, signature),
+            difficulty,
+            expires_at,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        challenge: Option<&str>,
+        nonce: Option<&str>,
+        to_address: &str,
+    ) -> Result<(), PowError> {
+        let (Some(challenge), Some(nonce)) = (challenge, nonce) else {
+            return Err(PowError::Missing);
+        };
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN {
+            return Err(PowError::Malformed);
+        }
+
+        let (payload, signature) = challenge.rsplit_once('.').ok_or(PowError::Malformed)?;
+        let signature = hex::decode(signature).map_err(|_| PowError::Malformed)?;
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| PowError::BadSignature)?;
+
+        let mut fields = payload.split('.').skip(1);
+        let difficulty: u8 = fields
+            .next()
+            .and_then(|d| d.parse().ok())
+            .ok_or(PowError::Malformed)?;
+        let expires_at: i64 = fields
+            .next()
+            .and_then(|e| e.parse().ok())
+            .ok_or(PowError::Malformed)?;
+
+        let now = Utc::now().timestamp();
+        if expires_at < now {
+            return Err(PowError::Expired);
+        }
+
+        let hash = Sha256::digest(format!("{}:{}:{}", challenge, to_address, nonce));
+        if leading_zero_bits(&hash) < u32::from(difficulty) {
+            return Err(PowError::Insufficient);
+        }
+
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        spent.retain(|_, expiry| *expiry >= now);
+        if spent.insert(challenge.to_owned(), expires_at).is_some() {
+            return Err(PowError::Replayed);
+        }
+
+        Ok(())
+    }
+
+    /// Records an issued challenge and returns the difficulty for it.
+    fn record_issue(&self) -> u8 {
+        let mut issued = self.issued.lock().unwrap_or_else(|e| e.into_inner());
+        while issued.front().is_some_and(|at| at.elapsed() >= LOAD_WINDOW) {
+            issued.pop_front();
+        }
+        issued.push_back(Instant::now());
+
+        let load = issued.len() as u32 / self.load_threshold;
+        let extra = if load == 0 { 0 } else { load.ilog2() + 1 };
+
+        self.base_difficulty
+            .saturating_add(u8::try_from(extra).unwrap_or(u8::MAX))
+            .min(self.max_difficulty)
+    }
+
+    fn mac(&self, payload: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "1111recipient";
+
+    fn enabled(vars: &[(&str, &str)]) -> ProofOfWork {
+        let mut all = vec![("POW_DIFFICULTY", "4"), ("POW_SECRET", "test-secret")];
+        all.extend_from_slice(vars);
+        ProofOfWork::from_config(&AppConfig::for_tests(&all)).expect("proof of work enabled")
+    }
+
+    /// First nonce whose hash has `enough` (or not) leading zero bits.
+    fn find_nonce(challenge: &Challenge, enough: bool) -> String {
+        (0u64..)
+            .map(|n| n.to_string())
+            .find(|nonce| {
+                let hash = Sha256::digest(format!("{}:{}:{}", challenge.challenge, ADDRESS, nonce));
+                (leading_zero_bits(&hash) >= u32::from(challenge.difficulty)) == enough
+            })
+            .unwrap()
+    }
+
+    fn signed(pow: &ProofOfWork, payload: &str) -> Challenge {
+        let signature = hex::encode(pow.mac(payload).finalize().into_bytes());
+        let mut fields = payload.split('.').skip(1);
+        Challenge {
+            challenge: format!("{}.{}", payload, signature),
+            difficulty: fields.next().unwrap().parse().unwrap(),
+            expires_at: fields.next().unwrap().parse().unwrap(),
+        }
+    }
+
+    fn verify(pow: &ProofOfWork, challenge: &Challenge, nonce: &str) -> Result<(), PowError> {
+        pow.verify(Some(&challenge.challenge), Some(nonce), ADDRESS)
+    }
+
+    #[test]
+    fn accepts_a_valid_solution() {
+        let pow = enabled(&[]);
+        let challenge = pow.issue();
+        assert_eq!(challenge.difficulty, 4);
+
+        let nonce = find_nonce(&challenge, true);
+        assert!(verify(&pow, &challenge, &nonce).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_tampered_signature() {
+        let pow = enabled(&[]);
+        let challenge = pow.issue();
+
+        // Lowering the difficulty invalidates the signature.
+        let (payload, signature) = challenge.challenge.rsplit_once('.').unwrap();
+        let (salt, rest) = payload.split_once('.').unwrap();
+        let (_, expires_at) = rest.split_once('.').unwrap();
+        let easier = Challenge {
+            challenge: format!("{}.0.{}.{}", salt, expires_at, signature),
+            ..challenge.clone()
+        };
+        assert!(matches!(
+            verify(&pow, &easier, "1"),
+            Err(PowError::BadSignature)
+        ));
+
+        // So does a challenge signed with another key.
+        let other = enabled(&[("POW_SECRET", "other-secret")]).issue();
+        let nonce = find_nonce(&other, true);
+        assert!(matches!(
+            verify(&pow, &other, &nonce),
+            Err(PowError::BadSignature)
+        ));
+    }
+
+    #[test]
+    fn rejects_an_expired_challenge() {
+        let pow = enabled(&[]);
+        let expired_at = Utc::now().timestamp() - 1;
+        let challenge = signed(&pow, &format!("00ff.4.{}", expired_at));
+
+        let nonce = find_nonce(&challenge, true);
+        assert!(matches!(
+            verify(&pow, &challenge, &nonce),
+            Err(PowError::Expired)
+        ));
+    }
+
+    #[test]
+    fn rejects_insufficient_leading_zero_bits() {
+        let pow = enabled(&[("POW_DIFFICULTY", "8")]);
+        let challenge = pow.issue();
+
+        let nonce = find_nonce(&challenge, false);
+        assert!(matches!(
+            verify(&pow, &challenge, &nonce),
+            Err(PowError::Insufficient)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_replayed_solution() {
+        let pow = enabled(&[]);
+        let challenge = pow.issue();
+        let nonce = find_nonce(&challenge, true);
+
+        assert!(verify(&pow, &challenge, &nonce).is_ok());
+        assert!(matches!(
+            verify(&pow, &challenge, &nonce),
+            Err(PowError::Replayed)
+        ));
+    }
+
+    #[test]
+    fn difficulty_rises_with_load() {
+        let pow = enabled(&[("POW_LOAD_THRESHOLD", "2"), ("POW_MAX_DIFFICULTY", "6")]);
+
+        let difficulties: Vec<u8> = (0..10).map(|_| pow.issue().difficulty).collect();
+        // One extra bit at the threshold, another at each doubling, capped.
+        assert_eq!(difficulties, [4, 5, 5, 6, 6, 6, 6, 6, 6, 6]);
+    }
+
+    #[test]
+    fn counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0x00, 0x00, 0x80]), 16);
+        assert_eq!(leading_zero_bits(&[0x0f, 0x00]), 4);
+        assert_eq!(leading_zero_bits(&[0xff]), 0);
+    }
+}
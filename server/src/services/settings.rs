@@ -0,0 +1,58 @@
+use crate::config::AppConfig;
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Settings that can change while the faucet is running. Everything else
+/// stays in `AppConfig`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeSettings {
+    pub paused: bool,
+    pub maintenance_message: Option<String>,
+    /// Set by the balance monitor while the faucet wallets are below
+    /// `BALANCE_CRITICAL_THRESHOLD`; independent of `paused`.
+    pub low_balance: bool,
+    pub faucet_amount: u64,
+    pub faucet_max_balance: u64,
+}
+
+/// Shared, atomically swapped `RuntimeSettings`. Readers get a consistent
+/// snapshot without locking; writers replace the whole value.
+#[derive(Clone)]
+pub struct FaucetSettings {
+    current: Arc<ArcSwap<RuntimeSettings>>,
+}
+
+impl FaucetSettings {
+    pub fn new(config: &AppConfig) -> Self {
+        let settings = RuntimeSettings {
+            paused: false,
+            maintenance_message: None,
+            low_balance: false,
+            faucet_amount: config.faucet_amount,
+            faucet_max_balance: config.faucet_max_balance,
+        };
+
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(settings)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<RuntimeSettings> {
+        self.current.load_full()
+    }
+
+    /// Applies `f` to a copy of the current settings and stores the result.
+    /// `f` may run more than once if another update races with it.
+    pub fn update<F>(&self, f: F) -> Arc<RuntimeSettings>
+    where
+        F: Fn(&mut RuntimeSettings),
+    {
+        self.current.rcu(|current| {
+            let mut next = RuntimeSettings::clone(current);
+            f(&mut next);
+            next
+        });
+        self.load()
+    }
+}
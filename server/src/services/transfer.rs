@@ -0,0 +1,333 @@
+use crate::config::AppConfig;
+use crate::services::ledger::CooldownReservation;
+use crate::services::metrics::Metrics;
+use crate::services::node_cli::{NodeCliError, NodeCliService};
+use crate::services::settings::FaucetSettings;
+use crate::services::wallets::{WalletLease, WalletPool};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, instrument, warn, Instrument, Span};
+
+#[derive(Debug, Clone)]
+pub enum TransferError {
+    /// The submission queue is full; the transfer was not attempted.
+    QueueFull,
+    Node(NodeCliError),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueFull => write!(f, "Submission queue is full"),
+            Self::Node(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {}
+
+impl From<NodeCliError> for TransferError {
+    fn from(e: NodeCliError) -> Self {
+        Self::Node(e)
+    }
+}
+
+/// How often `drain` checks whether the pending transfers have finished.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A transfer that was queued for submission but has not finished.
+#[derive(Debug, Clone)]
+pub struct PendingTransfer {
+    pub to_address: String,
+    /// A wallet was assigned and the deploy started, so it may reach the
+    /// node even if the faucet stops waiting for it.
+    pub deploying: bool,
+}
+
+type PendingTransfers = Arc<Mutex<HashMap<u64, PendingTransfer>>>;
+
+/// Keeps a submission listed in the pending transfers until it is dropped.
+struct PendingGuard {
+    id: u64,
+    pending: PendingTransfers,
+}
+
+impl PendingGuard {
+    fn mark_deploying(&self) {
+        if let Some(transfer) = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(&self.id)
+        {
+            transfer.deploying = true;
+        }
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.id);
+    }
+}
+
+struct Submission {
+    reservation: CooldownReservation,
+    reply: oneshot::Sender<Result<String, NodeCliError>>,
+    /// The caller's span, so the deploy shows up in the caller's trace.
+    span: Span,
+    guard: PendingGuard,
+}
+
+/// Handle to the submission actor, the only user of the faucet keys.
+/// Transfers from synchronous `/transfer` requests and from the job workers
+/// are queued here and deployed at most `SUBMISSION_CONCURRENCY` at a time
+/// per wallet, so a burst of requests does not race parallel deploys from
+/// one wallet.
+#[derive(Clone)]
+pub struct TransferService {
+    sender: mpsc::Sender<Submission>,
+    retry_after: Duration,
+    pending: PendingTransfers,
+    next_id: Arc<AtomicU64>,
+}
+
+impl TransferService {
+    pub fn start(
+        config: &AppConfig,
+        settings: FaucetSettings,
+        node_cli: NodeCliService,
+        wallets: WalletPool,
+        metrics: Metrics,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(config.submission_queue_size.max(1));
+
+        let submitter = Submitter {
+            node_cli,
+            settings,
+            wallets,
+            metrics,
+        };
+        tokio::spawn(Arc::new(submitter).run(receiver));
+
+        Self {
+            sender,
+            retry_after: Duration::from_secs(config.node_deploy_timeout_sec.max(1)),
+            pending: PendingTransfers::default(),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Suggested wait before retrying after `TransferError::QueueFull`:
+    /// roughly the time one queued deploy may take.
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+
+    /// Queues the transfer to the reserved address and waits for its deploy
+    /// ID, failing straight away when the queue is full. The reservation is
+    /// settled by the submission.
+    pub async fn try_execute(
+        &self,
+        reservation: CooldownReservation,
+    ) -> Result<String, TransferError> {
+        let permit = self
+            .sender
+            .try_reserve()
+            .map_err(|_| TransferError::QueueFull)?;
+
+        let (submission, response) = self.submission(reservation);
+        permit.send(submission);
+
+        Ok(Self::await_reply(response).await?)
+    }
+
+    /// Queues the transfer, waiting for room in the queue if necessary.
+    pub async fn execute(&self, reservation: CooldownReservation) -> Result<String, NodeCliError> {
+        let (submission, response) = self.submission(reservation);
+        self.sender
+            .send(submission)
+            .await
+            .map_err(|_| NodeCliError::Other("Submission queue closed".to_string()))?;
+
+        Self::await_reply(response).await
+    }
+
+    /// Transfers queued or being deployed.
+    pub fn pending(&self) -> Vec<PendingTransfer> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Waits until every pending transfer has finished or `deadline` has
+    /// passed, and returns the transfers still pending.
+    pub async fn drain(&self, deadline: Instant) -> Vec<PendingTransfer> {
+        loop {
+            let pending = self.pending();
+            let now = Instant::now();
+            if pending.is_empty() || now >= deadline {
+                return pending;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    fn submission(
+        &self,
+        reservation: CooldownReservation,
+    ) -> (Submission, oneshot::Receiver<Result<String, NodeCliError>>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                id,
+                PendingTransfer {
+                    to_address: reservation.address().to_owned(),
+                    deploying: false,
+                },
+            );
+
+        let (reply, response) = oneshot::channel();
+        let submission = Submission {
+            reservation,
+            reply,
+            span: Span::current(),
+            guard: PendingGuard {
+                id,
+                pending: self.pending.clone(),
+            },
+        };
+        (submission, response)
+    }
+
+    async fn await_reply(
+        response: oneshot::Receiver<Result<String, NodeCliError>>,
+    ) -> Result<String, NodeCliError> {
+        response.await.unwrap_or_else(|_| {
+            Err(NodeCliError::Other(
+                "Submission dropped before completion".to_string(),
+            ))
+        })
+    }
+}
+
+struct Submitter {
+    node_cli: NodeCliService,
+    settings: FaucetSettings,
+    wallets: WalletPool,
+    metrics: Metrics,
+}
+
+impl Submitter {
+    async fn run(self: Arc<Self>, mut receiver: mpsc::Receiver<Submission>) {
+        // A submission is only taken off the queue once a wallet is free, so
+        // the queue bound applies to everything not yet being deployed.
+        while let Some(submission) = receiver.recv().await {
+            let lease = match self.wallets.acquire().await {
+                Ok(lease) => lease,
+                Err(e) => {
+                    warn!(
+                        "FAUCET: Transfer to {} not submitted: {}",
+                        submission.reservation.address(),
+                        e
+                    );
+                    // Dropping the reservation gives the address back.
+                    let _ = submission.reply.send(Err(e));
+                    continue;
+                }
+            };
+
+            let Submission {
+                mut reservation,
+                reply,
+                span,
+                guard,
+            } = submission;
+            guard.mark_deploying();
+            reservation.mark_deploying();
+
+            let submitter = self.clone();
+            tokio::spawn(
+                async move {
+                    let result = submitter.submit(&lease, reservation).await;
+                    // The caller may have gone away (e.g. request timeout); the
+                    // transfer is still recorded in the ledger.
+                    let _ = reply.send(result);
+                    drop(lease);
+                    drop(guard);
+                }
+                .instrument(span),
+            );
+        }
+    }
+
+    #[instrument(
+        name = "transfer.submit",
+        skip_all,
+        fields(recipient = reservation.address(), wallet = %lease.wallet.address)
+    )]
+    async fn submit(
+        &self,
+        lease: &WalletLease,
+        reservation: CooldownReservation,
+    ) -> Result<String, NodeCliError> {
+        let wallet = &lease.wallet;
+        let to_address = reservation.address().to_owned();
+        let amount = self.settings.load().faucet_amount;
+        let deploy_id = match self
+            .node_cli
+            .transfer_funds(&to_address, amount, wallet.key())
+            .await
+        {
+            Ok(deploy_id) => deploy_id,
+            Err(e) => {
+                if matches!(e, NodeCliError::InsufficientFunds(_)) {
+                    warn!("FAUCET: Wallet {} is out of funds", wallet.address);
+                    wallet.mark_drained();
+                }
+                if e.proves_not_submitted() {
+                    if let Err(ledger_error) = reservation.release().await {
+                        error!(
+                            "FAUCET: Failed to release cooldown of {}: {}",
+                            to_address, ledger_error
+                        );
+                    }
+                } else {
+                    warn!(
+                        "FAUCET: Transfer to {} may still be deployed; keeping its cooldown",
+                        to_address
+                    );
+                }
+                return Err(e);
+            }
+        };
+        wallet.debit(amount);
+        self.metrics.record_transfer(amount);
+
+        info!(
+            "FAUCET: Transfer to {} deployed from {} with id {}",
+            to_address, wallet.address, deploy_id
+        );
+
+        if let Err(e) = reservation.record_transfer(amount, &deploy_id).await {
+            error!(
+                "FAUCET: Failed to record transfer {} to {} in ledger: {}",
+                deploy_id, to_address, e
+            );
+        }
+
+        Ok(deploy_id)
+    }
+}
@@ -0,0 +1,305 @@
+use crate::config::{AppConfig, WalletSelectionStrategy};
+use crate::secret::SecretString;
+use crate::services::node_cli::{NodeCliError, NodeCliService};
+use crate::services::settings::FaucetSettings;
+use anyhow::{anyhow, Result};
+use arc_swap::ArcSwap;
+use node_cli::utils::CryptoUtils;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// One faucet signing key. Deploys from different wallets are independent,
+/// so each wallet has its own submission slots.
+pub struct Wallet {
+    pub address: String,
+    key: SecretString,
+    slots: Arc<Semaphore>,
+    /// Last known balance; `None` until the first successful check.
+    balance: Mutex<Option<u128>>,
+}
+
+impl Wallet {
+    fn new(key: SecretString, concurrency: usize) -> Result<Self> {
+        let address = address_of(&key)?;
+
+        Ok(Self {
+            address,
+            key,
+            slots: Arc::new(Semaphore::new(concurrency)),
+            balance: Mutex::new(None),
+        })
+    }
+
+    pub fn key(&self) -> &SecretString {
+        &self.key
+    }
+
+    pub fn balance(&self) -> Option<u128> {
+        *self.balance.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_balance(&self, balance: u128) {
+        *self.balance.lock().unwrap_or_else(|e| e.into_inner()) = Some(balance);
+    }
+
+    /// Lowers the known balance after a transfer so selection reflects it
+    /// before the next balance check.
+    pub fn debit(&self, amount: u64) {
+        if let Some(balance) = self
+            .balance
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            *balance = balance.saturating_sub(amount as u128);
+        }
+    }
+
+    /// Marks the wallet as empty after the node rejected a deploy for lack
+    /// of funds; the next balance check corrects it.
+    pub fn mark_drained(&self) {
+        self.set_balance(0);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletStatus {
+    pub address: String,
+    pub balance: Option<String>,
+    pub eligible: bool,
+}
+
+/// A wallet reserved for one deploy; the slot is released on drop.
+pub struct WalletLease {
+    pub wallet: Arc<Wallet>,
+    _slot: OwnedSemaphorePermit,
+    released: Arc<Notify>,
+}
+
+impl Drop for WalletLease {
+    fn drop(&mut self) {
+        self.released.notify_one();
+    }
+}
+
+/// The faucet's signing keys. Transfers are spread across them by
+/// `WALLET_SELECTION_STRATEGY`, skipping wallets whose balance is below
+/// `WALLET_MIN_BALANCE` or the current faucet amount. Balances are refreshed
+/// by the `BalanceMonitor`.
+#[derive(Clone)]
+pub struct WalletPool {
+    wallets: Arc<ArcSwap<Vec<Arc<Wallet>>>>,
+    strategy: WalletSelectionStrategy,
+    min_balance: u64,
+    concurrency: usize,
+    next: Arc<AtomicUsize>,
+    released: Arc<Notify>,
+    settings: FaucetSettings,
+    node_cli: NodeCliService,
+}
+
+impl WalletPool {
+    pub fn new(
+        config: &AppConfig,
+        settings: FaucetSettings,
+        node_cli: NodeCliService,
+    ) -> Result<Self> {
+        let concurrency = config.submission_concurrency.max(1);
+        let wallets = build_wallets(&config.private_keys, concurrency, &[])?;
+
+        info!("FAUCET: Wallet pool has {} wallet(s)", wallets.len());
+
+        Ok(Self {
+            wallets: Arc::new(ArcSwap::from_pointee(wallets)),
+            strategy: config.wallet_selection_strategy,
+            min_balance: config.wallet_min_balance,
+            concurrency,
+            next: Arc::new(AtomicUsize::new(0)),
+            released: Arc::new(Notify::new()),
+            settings,
+            node_cli,
+        })
+    }
+
+    /// Replaces the set of keys. Wallets that stay keep their balance and
+    /// in-flight deploys; removed wallets finish the deploys they started.
+    pub fn set_keys(&self, keys: &[SecretString]) -> Result<()> {
+        let current = self.wallets.load_full();
+        let wallets = build_wallets(keys, self.concurrency, &current)?;
+
+        info!(
+            "FAUCET: Wallet pool changed ({} -> {} wallets)",
+            current.len(),
+            wallets.len()
+        );
+        self.wallets.store(Arc::new(wallets));
+        self.released.notify_one();
+        Ok(())
+    }
+
+    pub async fn refresh_balances(&self) {
+        let mut checks = JoinSet::new();
+        for wallet in self.wallets.load_full().iter().cloned() {
+            let node_cli = self.node_cli.clone();
+            checks.spawn(async move {
+                let balance = node_cli.get_balance(&wallet.address).await;
+                (wallet, balance)
+            });
+        }
+
+        while let Some(result) = checks.join_next().await {
+            let Ok((wallet, balance)) = result else {
+                continue;
+            };
+
+            match balance.map(|b| b.parse::<u128>()) {
+                Ok(Ok(balance)) => wallet.set_balance(balance),
+                Ok(Err(e)) => warn!(
+                    "FAUCET: Unparseable balance for wallet {}: {}",
+                    wallet.address, e
+                ),
+                Err(e) => warn!(
+                    "FAUCET: Balance check for wallet {} failed: {}",
+                    wallet.address, e
+                ),
+            }
+        }
+
+        self.released.notify_one();
+    }
+
+    /// Sum of the known wallet balances, or `None` if no balance is known.
+    pub fn total_balance(&self) -> Option<u128> {
+        self.wallets
+            .load()
+            .iter()
+            .filter_map(|wallet| wallet.balance())
+            .reduce(|total, balance| total.saturating_add(balance))
+    }
+
+    pub fn status(&self) -> Vec<WalletStatus> {
+        let threshold = self.threshold();
+
+        self.wallets
+            .load()
+            .iter()
+            .map(|wallet| WalletStatus {
+                address: wallet.address.clone(),
+                balance: wallet.balance().map(|b| b.to_string()),
+                eligible: is_eligible(wallet, threshold),
+            })
+            .collect()
+    }
+
+    /// Reserves a wallet for one deploy, waiting while every eligible wallet
+    /// is busy. Fails when no wallet has enough balance.
+    pub async fn acquire(&self) -> Result<WalletLease, NodeCliError> {
+        loop {
+            let released = self.released.notified();
+
+            let candidates = self.candidates();
+            if candidates.is_empty() {
+                return Err(NodeCliError::InsufficientFunds(
+                    "no faucet wallet has enough balance".to_string(),
+                ));
+            }
+
+            for wallet in candidates {
+                if let Ok(slot) = wallet.slots.clone().try_acquire_owned() {
+                    return Ok(WalletLease {
+                        wallet,
+                        _slot: slot,
+                        released: self.released.clone(),
+                    });
+                }
+            }
+
+            released.await;
+        }
+    }
+
+    /// A wallet must cover at least one transfer, and `WALLET_MIN_BALANCE`
+    /// when that is higher.
+    fn threshold(&self) -> u128 {
+        self.settings.load().faucet_amount.max(self.min_balance) as u128
+    }
+
+    /// Eligible wallets in the order they should be tried.
+    fn candidates(&self) -> Vec<Arc<Wallet>> {
+        let threshold = self.threshold();
+        let mut wallets: Vec<Arc<Wallet>> = self
+            .wallets
+            .load()
+            .iter()
+            .filter(|wallet| is_eligible(wallet, threshold))
+            .cloned()
+            .collect();
+
+        if wallets.is_empty() {
+            return wallets;
+        }
+
+        match self.strategy {
+            WalletSelectionStrategy::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % wallets.len();
+                wallets.rotate_left(start);
+            }
+            // Wallets not checked yet go last.
+            WalletSelectionStrategy::HighestBalance => {
+                wallets.sort_by_key(|wallet| std::cmp::Reverse(wallet.balance()));
+            }
+        }
+
+        wallets
+    }
+}
+
+/// Wallets whose balance is not known yet are given the benefit of the
+/// doubt, so an observer outage does not stop the faucet.
+fn is_eligible(wallet: &Wallet, threshold: u128) -> bool {
+    wallet.balance().is_none_or(|balance| balance >= threshold)
+}
+
+fn build_wallets(
+    keys: &[SecretString],
+    concurrency: usize,
+    existing: &[Arc<Wallet>],
+) -> Result<Vec<Arc<Wallet>>> {
+    let mut wallets: Vec<Arc<Wallet>> = Vec::with_capacity(keys.len());
+
+    for (index, key) in keys.iter().enumerate() {
+        let wallet = Wallet::new(key.clone(), concurrency)
+            .map_err(|e| anyhow!("Invalid faucet private key #{}: {}", index + 1, e))?;
+
+        if wallets.iter().any(|w| w.address == wallet.address) {
+            warn!(
+                "FAUCET: Ignoring duplicate key for wallet {}",
+                wallet.address
+            );
+            continue;
+        }
+
+        let wallet = existing
+            .iter()
+            .find(|w| w.address == wallet.address)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(wallet));
+        wallets.push(wallet);
+    }
+
+    Ok(wallets)
+}
+
+fn address_of(key: &SecretString) -> Result<String> {
+    let secret_key =
+        CryptoUtils::decode_private_key(key.expose_secret()).map_err(|e| anyhow!("{}", e))?;
+    let public_key = CryptoUtils::derive_public_key(&secret_key);
+    let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
+
+    CryptoUtils::generate_rev_address(&public_key_hex).map_err(|e| anyhow!("{}", e))
+}
@@ -0,0 +1,135 @@
+use crate::config::{AppConfig, LogFormat, OtlpProtocol};
+use anyhow::{Context, Result};
+use axum::{
+    extract::MatchedPath,
+    http::{Request, Response},
+};
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator, runtime, trace::TracerProvider, Resource,
+};
+use std::time::Duration;
+use tracing::{field::Empty, info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// Owns the trace exporter, if any. Spans still buffered are flushed by
+/// `shutdown`.
+pub struct Telemetry {
+    provider: Option<TracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("FAUCET: Failed to flush trace exporter: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber: `RUST_LOG` filtering, text or JSON log
+/// output and, when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, span export over
+/// OTLP/HTTP.
+pub fn init(config: &AppConfig) -> Result<Telemetry> {
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "asi_faucet=info,tower_http=debug,axum=debug".into());
+
+    let fmt_layer = match config.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_target(true).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_target(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    };
+
+    let provider = config
+        .otlp_endpoint
+        .as_ref()
+        .map(|endpoint| tracer_provider(config, endpoint.expose_secret()))
+        .transpose()?;
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    });
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()
+        .context("Failed to install tracing subscriber")?;
+
+    Ok(Telemetry { provider })
+}
+
+fn tracer_provider(config: &AppConfig, endpoint: &str) -> Result<TracerProvider> {
+    let protocol = match config.otlp_protocol {
+        OtlpProtocol::HttpProtobuf => Protocol::HttpBinary,
+        OtlpProtocol::HttpJson => Protocol::HttpJson,
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .context("Failed to create OTLP span exporter")?;
+
+    // Requests carrying a W3C `traceparent` header continue the caller's
+    // trace.
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            config.otel_service_name.clone(),
+        )]))
+        .build())
+}
+
+/// Span covering one HTTP request, named after the matched route so
+/// addresses and IDs do not end up in span names.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or("unmatched");
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let span = info_span!(
+        "http.request",
+        otel.name = format!("{} {}", request.method(), route),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        http.request.method = %request.method(),
+        http.route = route,
+        http.response.status_code = Empty,
+        request_id = request_id,
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+
+    span
+}
+
+pub fn record_response<B>(response: &Response<B>, _latency: Duration, span: &Span) {
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+}
@@ -1,13 +1,48 @@
-use anyhow::{bail, Result};
-use rand::{rng, seq::SliceRandom};
-use reqwest::Client;
 use std::time::Duration;
 
-#[derive(Debug, Clone, serde::Deserialize)]
+pub const TOKEN_SYMBOL: &str = "ASI";
+
+/// Amounts and balances are kept in the token's smallest unit,
+/// 1 ASI = 10^8.
+pub const TOKEN_DECIMALS: u32 = 8;
+
+/// Requests that take longer are answered with 408 and their handler dropped.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(7);
+
+/// Formats an amount in the smallest unit with `TOKEN_DECIMALS` decimal
+/// places, trailing zeros trimmed (`150000000` is `"1.5"`).
+pub fn format_token_amount(raw: u128) -> String {
+    let unit = 10u128.pow(TOKEN_DECIMALS);
+    let whole = raw / unit;
+    let fraction = raw % unit;
+
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = TOKEN_DECIMALS as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
 pub struct NodeSocket {
     pub host: String,
     pub grpc_port: u16,
     pub http_port: u16,
+    pub weight: Option<u32>,
+}
+
+impl NodeSocket {
+    pub fn weight(&self) -> u32 {
+        self.weight.unwrap_or(1)
+    }
+
+    /// Same validator, ignoring settings such as the weight.
+    pub fn same_endpoint(&self, other: &NodeSocket) -> bool {
+        self.host == other.host
+            && self.grpc_port == other.grpc_port
+            && self.http_port == other.http_port
+    }
 }
 
 // simple validation, proper research needed
@@ -18,31 +53,3 @@ pub fn validate_deploy_id(deploy_id: &str) -> bool {
     }
     deploy_id.chars().all(|c| c.is_ascii_alphanumeric())
 }
-
-async fn is_node_available(client: &Client, node: &NodeSocket) -> bool {
-    let url = format!("http://{}:{}/status", node.host, node.http_port);
-    match client.get(&url).send().await {
-        Ok(resp) => resp.status().is_success(),
-        Err(_) => false,
-    }
-}
-
-pub async fn choose_random_node(nodes: &[NodeSocket]) -> Result<&NodeSocket> {
-    if nodes.is_empty() {
-        bail!("No available node sockets");
-    }
-
-    let client = Client::builder().timeout(Duration::from_secs(2)).build()?;
-
-    let mut indices: Vec<usize> = (0..nodes.len()).collect();
-    indices.shuffle(&mut rng());
-
-    for &i in &indices {
-        let node = &nodes[i];
-        if is_node_available(&client, node).await {
-            return Ok(node);
-        }
-    }
-
-    bail!("No reachable nodes");
-}